@@ -1 +1,14 @@
+use anchor_lang::prelude::*;
+
 pub const QUOTE_DECIMALS: u8 = 6; // USD decimals for paper dollars
+pub const LIQUIDATION_PENALTY_BPS: u16 = 100; // 1% of the liquidated position value
+pub const KEEPER_REWARD_BPS: u16 = 5_000; // keeper's share of the liquidation penalty
+pub const MAX_TRADING_FEE_BPS: u16 = 1_000; // cap for taker fee and half spread, 10%
+pub const USE_MARKET_FEE: u16 = 0xFFFF; // league fee override sentinel, falls back to the market
+
+// Owners accepted for price feed accounts
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ"); // Pyth receiver on base layer
+pub const ER_PRICE_PROGRAM_ID: Pubkey = pubkey!("PriCems5tHihc6UDXDjzjeawomAwBduWMGAi8ZUjppd"); // realtime price feeds on ER
+
+// Owner of delegated accounts on the base layer
+pub const DELEGATION_PROGRAM_ID: Pubkey = pubkey!("DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh");
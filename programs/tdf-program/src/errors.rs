@@ -38,4 +38,84 @@ pub enum ErrorCode {
     PositionNotOpened,
     #[msg("Position already closed")]
     PositionAlreadyClosed,
+    #[msg("Reward already claimed")]
+    RewardAlreadyClaimed,
+    #[msg("Not eligible for reward")]
+    NotEligibleForReward,
+    #[msg("Invalid token account")]
+    InvalidTokenAccount,
+    #[msg("Invalid fee bps")]
+    InvalidFeeBps,
+    #[msg("Invalid treasury")]
+    InvalidTreasury,
+    #[msg("Invalid payout schedule")]
+    InvalidPayoutSchedule,
+    #[msg("Invalid participant limits")]
+    InvalidParticipantLimits,
+    #[msg("Minimum participants met")]
+    MinParticipantsMet,
+    #[msg("Entry already refunded")]
+    EntryAlreadyRefunded,
+    #[msg("League is full")]
+    LeagueFull,
+    #[msg("Registration closed")]
+    RegistrationClosed,
+    #[msg("Invalid oracle config")]
+    InvalidOracleConfig,
+    #[msg("Stale oracle price")]
+    StaleOraclePrice,
+    #[msg("Oracle confidence too wide")]
+    OracleConfidenceTooWide,
+    #[msg("Division by zero")]
+    DivisionByZero,
+    #[msg("Invalid close size")]
+    InvalidCloseSize,
+    #[msg("Invalid position size")]
+    InvalidPositionSize,
+    #[msg("Invalid maintenance margin")]
+    InvalidMaintenanceMargin,
+    #[msg("Participant is not liquidatable")]
+    NotLiquidatable,
+    #[msg("Invalid keeper")]
+    InvalidKeeper,
+    #[msg("Invalid trigger price")]
+    InvalidTriggerPrice,
+    #[msg("Trigger price not reached")]
+    TriggerNotReached,
+    #[msg("Invalid limit price")]
+    InvalidLimitPrice,
+    #[msg("Invalid order expiry")]
+    InvalidOrderExpiry,
+    #[msg("Order already placed")]
+    OrderAlreadyPlaced,
+    #[msg("Order is not open")]
+    OrderNotOpen,
+    #[msg("Order expired")]
+    OrderExpired,
+    #[msg("Limit price not reached")]
+    LimitPriceNotReached,
+    #[msg("Invalid market")]
+    InvalidMarket,
+    #[msg("Market not in league")]
+    MarketNotInLeague,
+    #[msg("Market is not active")]
+    MarketNotActive,
+    #[msg("Invalid participant")]
+    InvalidParticipant,
+    #[msg("Position is still open")]
+    PositionStillOpen,
+    #[msg("Invalid max positions")]
+    InvalidMaxPositions,
+    #[msg("Invalid position mode")]
+    InvalidPositionMode,
+    #[msg("Invalid trading fee")]
+    InvalidTradingFee,
+    #[msg("Margin mode mismatch")]
+    MarginModeMismatch,
+    #[msg("Order not expired")]
+    OrderNotExpired,
+    #[msg("Pages must be updated in order, starting at offset 0")]
+    InvalidPageOffset,
+    #[msg("Oracle price update is not fully verified")]
+    InsufficientOracleVerification,
 }
@@ -0,0 +1,259 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Direction, MarginMode};
+
+#[event]
+pub struct MarketCreated {
+    pub market: Pubkey,
+    pub symbol: [u8; 16],
+    pub price_feed: Pubkey,
+    pub decimals: u8,
+    pub max_leverage: u8,
+    pub maintenance_margin_bps: u16,
+    pub taker_fee_bps: u16,
+    pub half_spread_bps: u16,
+    pub listed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketUpdated {
+    pub market: Pubkey,
+    pub symbol: [u8; 16],
+    pub decimals: u8,
+    pub is_active: bool,
+    pub max_leverage: u8,
+    pub maintenance_margin_bps: u16,
+    pub taker_fee_bps: u16,
+    pub half_spread_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketDeleted {
+    pub market: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LeagueCreated {
+    pub league: Pubkey,
+    pub creator: Pubkey,
+    pub entry_token_mint: Pubkey,
+    pub entry_amount: i64,
+    pub virtual_on_deposit: i64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub max_participants: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LeagueStarted {
+    pub league: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LeagueCancelled {
+    pub league: Pubkey,
+    pub cancelled_by: Pubkey, // creator or admin
+    pub participant_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LeagueClosed {
+    pub league: Pubkey,
+    pub total_reward_amount: u64,
+    pub protocol_fee_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParticipantJoined {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub participant: Pubkey,
+    pub participant_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardClaimed {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub participant: Pubkey,
+    pub rank: u16, // 1-based rank on the equity leaderboard
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EntryRefunded {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub participant: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionOpened {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub position: Pubkey,
+    pub market: Pubkey,
+    pub direction: Direction,
+    pub margin_mode: MarginMode,
+    pub size: i64,
+    pub entry_price: i64, // price in price-decimal (1e6)
+    pub notional: i64,
+    pub leverage: u8,
+    pub fee: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionIncreased {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub position: Pubkey,
+    pub market: Pubkey,
+    pub added_size: i64,
+    pub fill_price: i64,  // price in price-decimal (1e6)
+    pub entry_price: i64, // new average entry price
+    pub size: i64,        // size after the increase
+    pub fee: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionTriggersUpdated {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub position: Pubkey,
+    pub stop_loss_price: i64,   // 0 if not set
+    pub take_profit_price: i64, // 0 if not set
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionClosed {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub position: Pubkey,
+    pub market: Pubkey,
+    pub close_size: i64,
+    pub close_price: i64, // price in price-decimal (1e6)
+    pub realized_pnl: i64,
+    pub fee: i64,
+    pub remaining_size: i64, // 0 when fully closed
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionArchived {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub position: Pubkey,
+    pub market: Pubkey,
+    pub seq_num: u64,
+    pub direction: Direction,
+    pub margin_mode: MarginMode,
+    pub leverage: u8,
+    pub entry_price: i64,
+    pub entry_size: i64,
+    pub closed_size: i64,
+    pub closed_price: i64,
+    pub closed_pnl: i64,
+    pub liquidation_penalty: i64,
+    pub opened_at: i64, // 0 if the position was never opened
+    pub closed_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionLiquidated {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub position: Pubkey,
+    pub market: Pubkey,
+    pub margin_mode: MarginMode,
+    pub size: i64,
+    pub price: i64, // price in price-decimal (1e6)
+    pub realized_pnl: i64,
+    pub penalty: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderPlaced {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub order: Pubkey,
+    pub market: Pubkey,
+    pub seq_num: u64,
+    pub direction: Direction,
+    pub margin_mode: MarginMode,
+    pub size: i64,
+    pub leverage: u8,
+    pub limit_price: i64, // price in price-decimal (1e6)
+    pub reserved_margin: i64,
+    pub expiry_ts: i64, // 0 if good till cancelled
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderFilled {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub order: Pubkey,
+    pub position: Pubkey,
+    pub market: Pubkey,
+    pub keeper: Pubkey, // signer who cranked the fill
+    pub size: i64,
+    pub limit_price: i64,  // fill price, in price-decimal (1e6)
+    pub oracle_price: i64, // oracle price that crossed the limit
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderCancelled {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub order: Pubkey,
+    pub cancelled_by: Pubkey, // owner, or anyone once expired
+    pub released_margin: i64, // 0 if a liquidation already released it
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParticipantUpdated {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub unrealized_pnl: i64,
+    pub used_margin: i64,
+    pub equity: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParticipantLiquidated {
+    pub league: Pubkey,
+    pub user: Pubkey,   // liquidated participant's owner
+    pub keeper: Pubkey, // signer who cranked the liquidation
+    pub positions_liquidated: u16,
+    pub total_penalty: i64,
+    pub keeper_reward: i64, // reward points credited to the keeper account
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LeaderboardUpdated {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub equity: i64,
+    pub volume: i64,
+    pub timestamp: i64,
+}
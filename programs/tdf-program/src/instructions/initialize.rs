@@ -3,6 +3,8 @@ use anchor_lang::prelude::*;
 use crate::state::{GlobalConfig, GLOBAL_CONFIG_SEED, GLOBAL_CONFIG_SPACE};
 
 pub fn initialize(ctx: Context<Initialize>, fee_bps: u16) -> Result<()> {
+    require!(fee_bps <= 10_000, crate::errors::ErrorCode::InvalidFeeBps);
+
     let global_config = &mut ctx.accounts.global_config;
     global_config.admin = ctx.accounts.admin.key();
     global_config.fee_bps = fee_bps;
@@ -27,6 +29,8 @@ pub fn update_admin(ctx: Context<UpdateAdmin>) -> Result<()> {
 }
 
 pub fn update_fee_bps(ctx: Context<UpdateFeeBps>, new_fee_bps: u16) -> Result<()> {
+    require!(new_fee_bps <= 10_000, crate::errors::ErrorCode::InvalidFeeBps);
+
     let global_config = &mut ctx.accounts.global_config;
     global_config.fee_bps = new_fee_bps;
     
@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::events::LeagueCancelled;
+use crate::state::{GlobalConfig, League, LeagueStatus, GLOBAL_CONFIG_SEED};
+
+pub fn cancel_league(ctx: Context<CancelLeague>) -> Result<()> {
+    let league = &mut ctx.accounts.league;
+
+    // Only the creator or the admin can cancel the league
+    let user = ctx.accounts.user.key();
+    require!(
+        user == league.creator || user == ctx.accounts.global_config.admin,
+        crate::errors::ErrorCode::NotLeagueCreator
+    );
+
+    // Pending leagues can always be cancelled.
+    // Active leagues only if the minimum participant count is not met by start time.
+    match league.status {
+        LeagueStatus::Pending => {}
+        LeagueStatus::Active => {
+            let now = Clock::get()?.unix_timestamp;
+            require!(
+                now >= league.start_ts && league.participant_count < league.min_participants,
+                crate::errors::ErrorCode::MinParticipantsMet
+            );
+        }
+        _ => return err!(crate::errors::ErrorCode::InvalidLeagueStatus),
+    }
+
+    league.status = LeagueStatus::Cancelled;
+    msg!(
+        "League {:?} cancelled with {} participants",
+        league.key(),
+        league.participant_count
+    );
+
+    emit!(LeagueCancelled {
+        league: league.key(),
+        cancelled_by: user,
+        participant_count: league.participant_count,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelLeague<'info> {
+    #[account(mut)]
+    pub league: Account<'info, League>,
+
+    #[account(seeds = [GLOBAL_CONFIG_SEED], bump = global_config.bump)]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
@@ -0,0 +1,263 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Token, TokenAccount};
+
+use crate::events::RewardClaimed;
+use crate::state::{
+    Leaderboard, League, LeagueStatus, Participant, PayoutSchedule, LEADERBOARD_SEED,
+    LEAGUE_SEED, PARTICIPANT_SEED,
+};
+
+pub fn claim_reward(ctx: Context<ClaimReward>) -> Result<()> {
+    let league = &ctx.accounts.league;
+    let leaderboard = &ctx.accounts.leaderboard;
+    let participant = &mut ctx.accounts.participant;
+
+    // Rewards are only distributed once the league is closed
+    require!(
+        league.status == LeagueStatus::Closed,
+        crate::errors::ErrorCode::InvalidLeagueStatus
+    );
+    require!(!participant.claimed, crate::errors::ErrorCode::RewardAlreadyClaimed);
+
+    // Verify the reward vault matches the league's reward vault
+    require_keys_eq!(
+        ctx.accounts.reward_vault.key(),
+        league.reward_vault,
+        crate::errors::ErrorCode::InvalidRewardVault
+    );
+    require_keys_eq!(
+        ctx.accounts.user_entry_token_account.mint,
+        league.entry_token_mint,
+        crate::errors::ErrorCode::InvalidTokenAccount
+    );
+    require_keys_eq!(
+        ctx.accounts.user_entry_token_account.owner,
+        participant.user,
+        crate::errors::ErrorCode::InvalidTokenAccount
+    );
+
+    // Final rank of the participant on the equity leaderboard
+    let rank = leaderboard
+        .topk_equity
+        .iter()
+        .position(|key| *key == participant.user)
+        .ok_or(crate::errors::ErrorCode::NotEligibleForReward)?;
+
+    let amount = calculate_reward_amount(league, leaderboard, rank)?;
+    require!(amount > 0, crate::errors::ErrorCode::NotEligibleForReward);
+
+    // Transfer the reward from the vault, signed by the league PDA
+    let league_seeds: &[&[u8]] = &[
+        LEAGUE_SEED,
+        league.creator.as_ref(),
+        league.id.as_bytes(),
+        &[league.bump],
+    ];
+    transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                to: ctx.accounts.user_entry_token_account.to_account_info(),
+                authority: league.to_account_info(),
+            },
+            &[league_seeds],
+        ),
+        amount,
+    )?;
+
+    participant.claimed = true;
+
+    msg!(
+        "Participant {:?} claimed reward {} at rank {}",
+        participant.user,
+        amount,
+        rank + 1
+    );
+
+    emit!(RewardClaimed {
+        league: league.key(),
+        user: participant.user,
+        participant: participant.key(),
+        rank: (rank + 1) as u16,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Share of the reward vault for the given (0-based) rank.
+/// Weights are normalized over the occupied paid ranks, so the pot is fully
+/// distributed even when fewer participants than paid ranks are on the leaderboard.
+/// Shares round down and rank 0 also takes the rounding remainder, so no dust is left in the vault.
+fn calculate_reward_amount(league: &League, leaderboard: &Leaderboard, rank: usize) -> Result<u64> {
+    let weights = payout_weights(
+        &league.payout_schedule,
+        &leaderboard.topk_equity_scores,
+        league.virtual_on_deposit,
+    );
+
+    let weight = weights.get(rank).copied().unwrap_or(0);
+    let total_weight: u128 = weights.iter().sum();
+    if total_weight == 0 || (weight == 0 && rank != 0) {
+        return Ok(0);
+    }
+
+    let pot = league.total_reward_amount as u128;
+    let share = |weight: u128| -> Result<u128> {
+        Ok(pot
+            .checked_mul(weight)
+            .ok_or(crate::errors::ErrorCode::MathOverflow)?
+            / total_weight)
+    };
+
+    let mut amount = share(weight)?;
+    if rank == 0 {
+        let mut distributed: u128 = 0;
+        for w in &weights {
+            distributed = distributed
+                .checked_add(share(*w)?)
+                .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+        }
+        amount = amount
+            .checked_add(pot - distributed)
+            .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    }
+
+    u64::try_from(amount).map_err(|_| crate::errors::ErrorCode::MathOverflow.into())
+}
+
+/// Payout weight of each occupied rank on the equity leaderboard.
+fn payout_weights(payout_schedule: &PayoutSchedule, scores: &[i64], virtual_on_deposit: i64) -> Vec<u128> {
+    match payout_schedule {
+        PayoutSchedule::Tiered { bps } => bps
+            .iter()
+            .take(scores.len())
+            .map(|b| *b as u128)
+            .collect(),
+        PayoutSchedule::LinearDecay { ranks } => (0..(*ranks as usize).min(scores.len()))
+            .map(|i| (*ranks as usize - i) as u128)
+            .collect(),
+        PayoutSchedule::ProportionalPnl { ranks } => {
+            let paid = (*ranks as usize).min(scores.len());
+            let pnl_weights: Vec<u128> = scores
+                .iter()
+                .take(paid)
+                .map(|score| score.saturating_sub(virtual_on_deposit).max(0) as u128)
+                .collect();
+
+            // Nobody finished in profit: split evenly across the paid ranks
+            if pnl_weights.iter().all(|w| *w == 0) {
+                vec![1; paid]
+            } else {
+                pnl_weights
+            }
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimReward<'info> {
+    #[account(
+        seeds = [LEAGUE_SEED, league.creator.as_ref(), league.id.as_bytes()],
+        bump = league.bump
+    )]
+    pub league: Account<'info, League>,
+
+    #[account(
+        seeds = [LEADERBOARD_SEED, league.key().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+
+    #[account(
+        mut,
+        seeds = [
+            PARTICIPANT_SEED,
+            league.key().as_ref(),
+            user.key().as_ref(),
+        ],
+        bump = participant.bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(mut)]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_entry_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{self, USD};
+
+    fn rewards(payout_schedule: PayoutSchedule, pot: u64, scores: Vec<i64>) -> Vec<u64> {
+        let league = test_fixtures::league(payout_schedule, pot);
+        let leaderboard = test_fixtures::leaderboard(scores);
+        (0..=leaderboard.topk_equity_scores.len())
+            .map(|rank| calculate_reward_amount(&league, &leaderboard, rank).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn tiered_splits_the_pot_by_bps() {
+        let tiered = PayoutSchedule::Tiered { bps: vec![5_000, 3_000, 2_000] };
+        let scores = vec![13_000 * USD, 12_000 * USD, 11_000 * USD];
+        // Ranks past the paid ones get nothing
+        assert_eq!(rewards(tiered, 900, scores), vec![450, 270, 180, 0]);
+    }
+
+    #[test]
+    fn rounding_remainder_goes_to_rank_zero() {
+        let tiered = PayoutSchedule::Tiered { bps: vec![5_000, 3_000, 2_000] };
+        let scores = vec![13_000 * USD, 12_000 * USD, 11_000 * USD];
+        // 500.5, 300.3 and 200.2 round down, the leftover unit goes to the winner
+        let amounts = rewards(tiered, 1_001, scores);
+        assert_eq!(amounts, vec![501, 300, 200, 0]);
+        assert_eq!(amounts.iter().sum::<u64>(), 1_001);
+    }
+
+    #[test]
+    fn tiered_renormalizes_when_fewer_participants_than_paid_ranks() {
+        let tiered = PayoutSchedule::Tiered { bps: vec![5_000, 3_000, 2_000] };
+        let scores = vec![13_000 * USD, 12_000 * USD];
+        // 5000 / 8000 and 3000 / 8000 of the pot, the rounding remainder goes to rank 0
+        assert_eq!(rewards(tiered, 900, scores), vec![563, 337, 0]);
+    }
+
+    #[test]
+    fn linear_decay_weights_ranks_down_to_one() {
+        let linear = PayoutSchedule::LinearDecay { ranks: 3 };
+        let scores = vec![13_000 * USD, 12_000 * USD, 11_000 * USD, 10_000 * USD];
+        assert_eq!(rewards(linear, 900, scores), vec![450, 300, 150, 0, 0]);
+    }
+
+    #[test]
+    fn proportional_pnl_pays_by_profit() {
+        let proportional = PayoutSchedule::ProportionalPnl { ranks: 2 };
+        // $3,000 and $1,000 profit on the $10,000 deposit, third place unpaid
+        let scores = vec![13_000 * USD, 11_000 * USD, 10_500 * USD];
+        assert_eq!(rewards(proportional, 900, scores), vec![675, 225, 0, 0]);
+    }
+
+    #[test]
+    fn proportional_pnl_splits_evenly_without_profit() {
+        let proportional = PayoutSchedule::ProportionalPnl { ranks: 2 };
+        let scores = vec![9_000 * USD, 8_000 * USD, 7_000 * USD];
+        assert_eq!(rewards(proportional, 900, scores), vec![450, 450, 0, 0]);
+    }
+
+    #[test]
+    fn empty_leaderboard_pays_nothing() {
+        let tiered = PayoutSchedule::Tiered { bps: vec![10_000] };
+        assert_eq!(rewards(tiered, 900, vec![]), vec![0]);
+    }
+}
@@ -1,10 +1,12 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::TokenAccount;
+use anchor_spl::token::{transfer, Token, TokenAccount};
 
-use crate::state::{League, LeagueStatus};
+use crate::events::LeagueClosed;
+use crate::state::{GlobalConfig, League, LeagueStatus, GLOBAL_CONFIG_SEED, LEAGUE_SEED};
 
 pub fn close_league(ctx: Context<CloseLeague>) -> Result<()> {
     let league = &mut ctx.accounts.league;
+    let global_config = &ctx.accounts.global_config;
 
     // Check if the league is active
     require!(
@@ -29,14 +31,63 @@ pub fn close_league(ctx: Context<CloseLeague>) -> Result<()> {
         crate::errors::ErrorCode::InvalidRewardVault
     );
 
+    // Verify the treasury token account belongs to the configured treasury
+    require_keys_eq!(
+        ctx.accounts.treasury_token_account.owner,
+        global_config.treasury,
+        crate::errors::ErrorCode::InvalidTreasury
+    );
+    require_keys_eq!(
+        ctx.accounts.treasury_token_account.mint,
+        league.entry_token_mint,
+        crate::errors::ErrorCode::InvalidTreasury
+    );
+
+    // Protocol fee = fee_bps of the league pot
+    let pot = ctx.accounts.reward_vault.amount;
+    let protocol_fee = u64::try_from(pot as u128 * global_config.fee_bps as u128 / 10_000)
+        .map_err(|_| crate::errors::ErrorCode::MathOverflow)?;
+
+    if protocol_fee > 0 {
+        let league_seeds: &[&[u8]] = &[
+            LEAGUE_SEED,
+            league.creator.as_ref(),
+            league.id.as_bytes(),
+            &[league.bump],
+        ];
+        transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.treasury_token_account.to_account_info(),
+                    authority: league.to_account_info(),
+                },
+                &[league_seeds],
+            ),
+            protocol_fee,
+        )?;
+    }
+
     // Fix the total reward amount at the time of closing
-    league.total_reward_amount = ctx.accounts.reward_vault.amount;
+    league.protocol_fee_amount = protocol_fee;
+    league.total_reward_amount = pot
+        .checked_sub(protocol_fee)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
     league.status = LeagueStatus::Closed;
 
+    emit!(LeagueClosed {
+        league: league.key(),
+        total_reward_amount: league.total_reward_amount,
+        protocol_fee_amount: league.protocol_fee_amount,
+        timestamp: now,
+    });
+
     msg!(
-        "League {:?} closed with total reward amount: {}",
+        "League {:?} closed with total reward amount: {}, protocol fee: {}",
         league.key(),
-        league.total_reward_amount
+        league.total_reward_amount,
+        league.protocol_fee_amount
     );
 
     Ok(())
@@ -47,9 +98,17 @@ pub struct CloseLeague<'info> {
     #[account(mut)]
     pub league: Account<'info, League>,
 
-    /// CHECK:
+    #[account(mut)]
     pub reward_vault: Account<'info, TokenAccount>,
 
+    #[account(seeds = [GLOBAL_CONFIG_SEED], bump = global_config.bump)]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    #[account(mut)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
     #[account(mut)]
     pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
 }
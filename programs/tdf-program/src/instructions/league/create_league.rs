@@ -2,9 +2,11 @@ use anchor_lang::prelude::*;
 use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
 use anchor_spl::token::Token;
 
+use crate::constants::{MAX_TRADING_FEE_BPS, USE_MARKET_FEE};
+use crate::events::LeagueCreated;
 use crate::state::{
-    Leaderboard, League, LeagueStatus, LEADERBOARD_SEED, LEADERBOARD_SPACE, LEAGUE_SEED,
-    LEAGUE_SPACE,
+    Leaderboard, League, LeagueStatus, Market, PayoutSchedule, PositionMode, LEADERBOARD_SEED,
+    LEADERBOARD_SPACE, LEAGUE_SEED, LEAGUE_SPACE, MARKET_SEED,
 };
 
 pub fn create_league(
@@ -15,10 +17,16 @@ pub fn create_league(
     virtual_on_deposit: i64,
     start_ts: i64,
     end_ts: i64,
+    late_join_cutoff_ts: i64,
     metadata_uri: String,
     max_participants: u32,
+    min_participants: u32,
     max_leverage: u8,
     k: u16,
+    payout_schedule: PayoutSchedule,
+    position_mode: PositionMode,
+    taker_fee_bps: u16,
+    half_spread_bps: u16,
 ) -> Result<()> {
     // validate inputs
     require!(
@@ -29,7 +37,23 @@ pub fn create_league(
         start_ts < end_ts,
         crate::errors::ErrorCode::InvalidTimeRange
     );
+    require!(
+        late_join_cutoff_ts == 0
+            || (start_ts <= late_join_cutoff_ts && late_join_cutoff_ts < end_ts),
+        crate::errors::ErrorCode::InvalidTimeRange
+    );
     require!(k <= 10, crate::errors::ErrorCode::InvalidKValue);
+    require!(
+        max_participants > 0 && min_participants <= max_participants,
+        crate::errors::ErrorCode::InvalidParticipantLimits
+    );
+    validate_payout_schedule(&payout_schedule, k)?;
+    validate_markets(&markets, ctx.remaining_accounts)?;
+    require!(
+        (taker_fee_bps == USE_MARKET_FEE || taker_fee_bps <= MAX_TRADING_FEE_BPS)
+            && (half_spread_bps == USE_MARKET_FEE || half_spread_bps <= MAX_TRADING_FEE_BPS),
+        crate::errors::ErrorCode::InvalidTradingFee
+    );
 
     let league = &mut ctx.accounts.league;
     let leaderboard = &mut ctx.accounts.leaderboard;
@@ -79,12 +103,20 @@ pub fn create_league(
     league.entry_amount = entry_amount;
     league.reward_vault = ctx.accounts.reward_vault.key();
     league.total_reward_amount = 0;
+    league.protocol_fee_amount = 0;
+    league.payout_schedule = payout_schedule;
     league.virtual_on_deposit = virtual_on_deposit;
     league.metadata_uri = metadata_uri;
     league.start_ts = start_ts;
     league.end_ts = end_ts;
+    league.late_join_cutoff_ts = late_join_cutoff_ts;
     league.max_participants = max_participants;
+    league.min_participants = min_participants;
+    league.participant_count = 0;
     league.max_leverage = max_leverage;
+    league.position_mode = position_mode;
+    league.taker_fee_bps = taker_fee_bps;
+    league.half_spread_bps = half_spread_bps;
     league.bump = ctx.bumps.league;
 
     leaderboard.league = league.key();
@@ -96,6 +128,73 @@ pub fn create_league(
     leaderboard.last_updated = Clock::get()?.unix_timestamp;
     leaderboard.bump = ctx.bumps.leaderboard;
 
+    emit!(LeagueCreated {
+        league: league.key(),
+        creator: league.creator,
+        entry_token_mint: league.entry_token_mint,
+        entry_amount,
+        virtual_on_deposit,
+        start_ts,
+        end_ts,
+        max_participants,
+        timestamp: leaderboard.last_updated,
+    });
+
+    Ok(())
+}
+
+/// Every league market must be an existing, active Market PDA of this program.
+/// Market accounts are passed as remaining accounts in the same order as `markets`.
+fn validate_markets(markets: &[Pubkey], market_accounts: &[AccountInfo]) -> Result<()> {
+    require!(
+        !markets.is_empty() && market_accounts.len() == markets.len(),
+        crate::errors::ErrorCode::InvalidMarket
+    );
+
+    for (i, (market_key, market_ai)) in markets.iter().zip(market_accounts.iter()).enumerate() {
+        require!(
+            !markets[..i].contains(market_key),
+            crate::errors::ErrorCode::InvalidMarket
+        );
+        require_keys_eq!(*market_key, market_ai.key(), crate::errors::ErrorCode::InvalidMarket);
+        require_keys_eq!(*market_ai.owner, crate::ID, crate::errors::ErrorCode::InvalidMarket);
+
+        let market = Market::try_deserialize(&mut &market_ai.data.borrow()[..])?;
+        let market_pda = Pubkey::create_program_address(
+            &[MARKET_SEED, market.price_feed.as_ref(), &[market.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| crate::errors::ErrorCode::InvalidMarket)?;
+        require_keys_eq!(*market_key, market_pda, crate::errors::ErrorCode::InvalidMarket);
+        require!(market.is_active, crate::errors::ErrorCode::MarketNotActive);
+    }
+
+    Ok(())
+}
+
+/// Paid ranks must fit within the leaderboard's top k,
+/// and fixed tiers must add up to the whole pot (10_000 bps).
+fn validate_payout_schedule(payout_schedule: &PayoutSchedule, k: u16) -> Result<()> {
+    match payout_schedule {
+        PayoutSchedule::Tiered { bps } => {
+            require!(
+                !bps.is_empty() && bps.len() <= k as usize,
+                crate::errors::ErrorCode::InvalidPayoutSchedule
+            );
+            let total_bps: u32 = bps.iter().map(|b| *b as u32).sum();
+            require!(
+                total_bps == 10_000,
+                crate::errors::ErrorCode::InvalidPayoutSchedule
+            );
+        }
+        PayoutSchedule::LinearDecay { ranks } | PayoutSchedule::ProportionalPnl { ranks } => {
+            require!(
+                *ranks > 0 && *ranks <= k,
+                crate::errors::ErrorCode::InvalidPayoutSchedule
+            );
+        }
+    }
+
     Ok(())
 }
 
@@ -133,4 +232,100 @@ pub struct CreateLeague<'info> {
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
+
+    // Remaining accounts:
+    // [market_0, market_1, ...] in the same order as `markets`
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorCode;
+    use crate::test_fixtures::{self, TestAccount};
+
+    fn assert_invalid(payout_schedule: PayoutSchedule, k: u16) {
+        assert_eq!(
+            validate_payout_schedule(&payout_schedule, k).unwrap_err(),
+            ErrorCode::InvalidPayoutSchedule.into()
+        );
+    }
+
+    #[test]
+    fn tiered_schedule_must_sum_to_the_whole_pot_within_k() {
+        validate_payout_schedule(&PayoutSchedule::Tiered { bps: vec![5_000, 3_000, 2_000] }, 3).unwrap();
+        validate_payout_schedule(&PayoutSchedule::Tiered { bps: vec![10_000] }, 1).unwrap();
+
+        assert_invalid(PayoutSchedule::Tiered { bps: vec![] }, 3);
+        assert_invalid(PayoutSchedule::Tiered { bps: vec![5_000, 3_000, 1_000] }, 3);
+        assert_invalid(PayoutSchedule::Tiered { bps: vec![5_000, 3_000, 3_000] }, 3);
+        // More paid tiers than leaderboard ranks
+        assert_invalid(PayoutSchedule::Tiered { bps: vec![5_000, 3_000, 2_000] }, 2);
+    }
+
+    #[test]
+    fn ranked_schedules_pay_between_one_and_k_ranks() {
+        validate_payout_schedule(&PayoutSchedule::LinearDecay { ranks: 1 }, 5).unwrap();
+        validate_payout_schedule(&PayoutSchedule::ProportionalPnl { ranks: 5 }, 5).unwrap();
+
+        assert_invalid(PayoutSchedule::LinearDecay { ranks: 0 }, 5);
+        assert_invalid(PayoutSchedule::LinearDecay { ranks: 6 }, 5);
+        assert_invalid(PayoutSchedule::ProportionalPnl { ranks: 0 }, 5);
+        assert_invalid(PayoutSchedule::ProportionalPnl { ranks: 11 }, 10);
+    }
+
+    /// Market account at its PDA, as listed by `create_market`.
+    fn market_account(is_active: bool) -> TestAccount {
+        let mut market = test_fixtures::market();
+        market.is_active = is_active;
+        let (key, bump) = Pubkey::find_program_address(&[MARKET_SEED, market.price_feed.as_ref()], &crate::ID);
+        market.bump = bump;
+        TestAccount::new(key, crate::ID, &market)
+    }
+
+    fn validate(markets: &[Pubkey], accounts: &mut [TestAccount]) -> Result<()> {
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        validate_markets(markets, &infos)
+    }
+
+    #[test]
+    fn league_markets_must_be_listed_and_active() {
+        let mut accounts = vec![market_account(true), market_account(true)];
+        let markets: Vec<Pubkey> = accounts.iter().map(|account| account.key).collect();
+        validate(&markets, &mut accounts).unwrap();
+
+        let mut inactive = vec![market_account(false)];
+        let markets = vec![inactive[0].key];
+        assert_eq!(validate(&markets, &mut inactive).unwrap_err(), ErrorCode::MarketNotActive.into());
+    }
+
+    #[test]
+    fn league_markets_must_match_their_accounts() {
+        let mut accounts = vec![market_account(true)];
+        let key = accounts[0].key;
+
+        assert_eq!(validate(&[], &mut []).unwrap_err(), ErrorCode::InvalidMarket.into());
+        // Missing, duplicated and mismatched markets
+        assert_eq!(validate(&[key, key], &mut accounts).unwrap_err(), ErrorCode::InvalidMarket.into());
+        let mut duplicated = vec![market_account(true), market_account(true)];
+        duplicated[1].key = duplicated[0].key;
+        assert_eq!(validate(&[key, key], &mut duplicated).unwrap_err(), ErrorCode::InvalidMarket.into());
+        assert_eq!(
+            validate(&[Pubkey::new_unique()], &mut accounts).unwrap_err(),
+            ErrorCode::InvalidMarket.into()
+        );
+    }
+
+    #[test]
+    fn league_markets_must_be_market_pdas_of_this_program() {
+        let mut foreign = vec![market_account(true)];
+        foreign[0].owner = Pubkey::new_unique();
+        let markets = vec![foreign[0].key];
+        assert_eq!(validate(&markets, &mut foreign).unwrap_err(), ErrorCode::InvalidMarket.into());
+
+        // Program-owned, but not at the market's PDA
+        let mut misplaced = vec![market_account(true)];
+        misplaced[0].key = Pubkey::new_unique();
+        let markets = vec![misplaced[0].key];
+        assert_eq!(validate(&markets, &mut misplaced).unwrap_err(), ErrorCode::InvalidMarket.into());
+    }
 }
@@ -1,14 +1,16 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{transfer, Token};
 
-use crate::state::{League, LeagueStatus, Participant, PARTICIPANT_SEED, PARTICIPANT_SPACE};
+use crate::events::ParticipantJoined;
+use crate::state::{
+    League, LeagueStatus, Participant, DEFAULT_MAX_POSITIONS, PARTICIPANT_SEED, PARTICIPANT_SPACE,
+};
 
 pub fn join_league(ctx: Context<JoinLeague>) -> Result<()> {
     let league = &mut ctx.accounts.league;
-    require!(
-        league.status == LeagueStatus::Active,
-        crate::errors::ErrorCode::InvalidLeagueStatus
-    );
+
+    let now = Clock::get()?.unix_timestamp;
+    check_registration_open(league, now)?;
 
     // try transfer entry token to the league
     transfer(
@@ -23,17 +25,60 @@ pub fn join_league(ctx: Context<JoinLeague>) -> Result<()> {
         league.entry_amount as u64,
     )?;
 
+    league.participant_count = league
+        .participant_count
+        .checked_add(1)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
     let participant = &mut ctx.accounts.participant;
 
     participant.league = league.key();
     participant.user = ctx.accounts.user.key();
     participant.claimed = false;
     participant.virtual_balance = league.virtual_on_deposit;
+    participant.max_positions = DEFAULT_MAX_POSITIONS;
     participant.positions = vec![];
     participant.topk_equity_index = 0xFFFF;
     participant.topk_volume_index = 0xFFFF;
     participant.bump = ctx.bumps.participant;
 
+    emit!(ParticipantJoined {
+        league: league.key(),
+        user: participant.user,
+        participant: participant.key(),
+        participant_count: league.participant_count,
+        timestamp: now,
+    });
+
+    msg!(
+        "Participant {:?} joined league {:?} ({}/{})",
+        participant.user,
+        league.key(),
+        league.participant_count,
+        league.max_participants
+    );
+
+    Ok(())
+}
+
+/// Registration is open while pending, and after start only until the late-join cutoff,
+/// as long as the league has room for another participant.
+fn check_registration_open(league: &League, now: i64) -> Result<()> {
+    match league.status {
+        LeagueStatus::Pending => {}
+        LeagueStatus::Active => {
+            require!(
+                now < league.late_join_cutoff_ts,
+                crate::errors::ErrorCode::RegistrationClosed
+            );
+        }
+        _ => return err!(crate::errors::ErrorCode::InvalidLeagueStatus),
+    }
+    require!(
+        league.participant_count < league.max_participants,
+        crate::errors::ErrorCode::LeagueFull
+    );
+
     Ok(())
 }
 
@@ -69,3 +114,62 @@ pub struct JoinLeague<'info> {
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorCode;
+    use crate::state::PayoutSchedule;
+    use crate::test_fixtures;
+
+    fn pending_league(max_participants: u32, participant_count: u32) -> League {
+        let mut league = test_fixtures::league(PayoutSchedule::LinearDecay { ranks: 1 }, 0);
+        league.status = LeagueStatus::Pending;
+        league.max_participants = max_participants;
+        league.participant_count = participant_count;
+        league
+    }
+
+    #[test]
+    fn joins_until_the_league_is_full() {
+        check_registration_open(&pending_league(2, 0), 0).unwrap();
+        check_registration_open(&pending_league(2, 1), 0).unwrap();
+        assert_eq!(
+            check_registration_open(&pending_league(2, 2), 0).unwrap_err(),
+            ErrorCode::LeagueFull.into()
+        );
+    }
+
+    #[test]
+    fn active_league_accepts_joins_only_before_the_cutoff() {
+        let mut league = pending_league(100, 1);
+        league.status = LeagueStatus::Active;
+        league.start_ts = 1_000;
+        league.late_join_cutoff_ts = 2_000;
+
+        check_registration_open(&league, 1_999).unwrap();
+        assert_eq!(
+            check_registration_open(&league, 2_000).unwrap_err(),
+            ErrorCode::RegistrationClosed.into()
+        );
+
+        // Without a cutoff, joining closes at start
+        league.late_join_cutoff_ts = 0;
+        assert_eq!(
+            check_registration_open(&league, 1_000).unwrap_err(),
+            ErrorCode::RegistrationClosed.into()
+        );
+    }
+
+    #[test]
+    fn closed_and_cancelled_leagues_reject_joins() {
+        let mut league = pending_league(100, 0);
+        for status in [LeagueStatus::Closed, LeagueStatus::Cancelled] {
+            league.status = status;
+            assert_eq!(
+                check_registration_open(&league, 0).unwrap_err(),
+                ErrorCode::InvalidLeagueStatus.into()
+            );
+        }
+    }
+}
@@ -3,9 +3,15 @@ mod start_league;
 mod close_league;
 mod join_league;
 mod update_leaderboard;
+mod claim_reward;
+mod cancel_league;
+mod refund_entry;
 
 pub use create_league::*;
 pub use start_league::*;
 pub use close_league::*;
 pub use join_league::*;
 pub use update_leaderboard::*;
+pub use claim_reward::*;
+pub use cancel_league::*;
+pub use refund_entry::*;
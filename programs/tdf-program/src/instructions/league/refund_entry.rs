@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Token, TokenAccount};
+
+use crate::events::EntryRefunded;
+use crate::state::{League, LeagueStatus, Participant, LEAGUE_SEED, PARTICIPANT_SEED};
+
+pub fn refund_entry(ctx: Context<RefundEntry>) -> Result<()> {
+    let league = &ctx.accounts.league;
+    let participant = &mut ctx.accounts.participant;
+
+    // Entry fees are only refunded for cancelled leagues
+    require!(
+        league.status == LeagueStatus::Cancelled,
+        crate::errors::ErrorCode::InvalidLeagueStatus
+    );
+    require!(!participant.claimed, crate::errors::ErrorCode::EntryAlreadyRefunded);
+
+    // Verify the reward vault matches the league's reward vault
+    require_keys_eq!(
+        ctx.accounts.reward_vault.key(),
+        league.reward_vault,
+        crate::errors::ErrorCode::InvalidRewardVault
+    );
+    require_keys_eq!(
+        ctx.accounts.user_entry_token_account.mint,
+        league.entry_token_mint,
+        crate::errors::ErrorCode::InvalidTokenAccount
+    );
+    require_keys_eq!(
+        ctx.accounts.user_entry_token_account.owner,
+        participant.user,
+        crate::errors::ErrorCode::InvalidTokenAccount
+    );
+
+    // Transfer the entry amount back from the vault, signed by the league PDA
+    let league_seeds: &[&[u8]] = &[
+        LEAGUE_SEED,
+        league.creator.as_ref(),
+        league.id.as_bytes(),
+        &[league.bump],
+    ];
+    transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.reward_vault.to_account_info(),
+                to: ctx.accounts.user_entry_token_account.to_account_info(),
+                authority: league.to_account_info(),
+            },
+            &[league_seeds],
+        ),
+        league.entry_amount as u64,
+    )?;
+
+    participant.claimed = true;
+
+    msg!(
+        "Participant {:?} refunded entry amount {}",
+        participant.user,
+        league.entry_amount
+    );
+
+    emit!(EntryRefunded {
+        league: league.key(),
+        user: participant.user,
+        participant: participant.key(),
+        amount: league.entry_amount as u64,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RefundEntry<'info> {
+    #[account(
+        seeds = [LEAGUE_SEED, league.creator.as_ref(), league.id.as_bytes()],
+        bump = league.bump
+    )]
+    pub league: Account<'info, League>,
+
+    #[account(
+        mut,
+        seeds = [
+            PARTICIPANT_SEED,
+            league.key().as_ref(),
+            user.key().as_ref(),
+        ],
+        bump = participant.bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(mut)]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_entry_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
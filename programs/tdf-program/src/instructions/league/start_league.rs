@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 
+use crate::events::LeagueStarted;
 use crate::state::{League, LeagueStatus};
 
 pub fn start_league(ctx: Context<StartLeague>) -> Result<()> {
@@ -22,6 +23,11 @@ pub fn start_league(ctx: Context<StartLeague>) -> Result<()> {
   }
 
   league.status = LeagueStatus::Active;
+
+  emit!(LeagueStarted {
+      league: league.key(),
+      timestamp: now,
+  });
   msg!("League {:?} started!", league.key());
 
   Ok(())
@@ -1,12 +1,21 @@
 use anchor_lang::prelude::*;
 
-use crate::state::{Leaderboard, LEADERBOARD_SEED, Participant};
+use crate::events::LeaderboardUpdated;
+use crate::state::{Leaderboard, League, LeagueStatus, LEADERBOARD_SEED, LEAGUE_SEED};
+use crate::utils::load_participant;
 
 pub fn update_leaderboard_with_participant(ctx: Context<UpdateLeaderboardWithParticipant>) -> Result<()> {
+    let league = &ctx.accounts.league;
     let leaderboard = &mut ctx.accounts.leaderboard;
-    let participant_info = &mut ctx.accounts.participant.to_account_info();
-    let mut data: &[u8] = &participant_info.try_borrow_data()?;
-    let participant = Participant::try_deserialize(&mut data)?;
+
+    // The final leaderboard of a closed or cancelled league must not change
+    require!(
+        league.status == LeagueStatus::Pending || league.status == LeagueStatus::Active,
+        crate::errors::ErrorCode::InvalidLeagueStatus
+    );
+
+    // Participant may be delegated, so it is validated by owner, PDA and league
+    let participant = load_participant(&ctx.accounts.participant, &leaderboard.league)?;
 
     let equity = participant.equity();
     let volume = participant.total_volume;
@@ -16,6 +25,14 @@ pub fn update_leaderboard_with_participant(ctx: Context<UpdateLeaderboardWithPar
 
     leaderboard.last_updated = Clock::get()?.unix_timestamp;
 
+    emit!(LeaderboardUpdated {
+        league: leaderboard.league,
+        user: participant.user,
+        equity,
+        volume,
+        timestamp: leaderboard.last_updated,
+    });
+
     msg!("Updated leaderboard at {} with participant: {:?}", leaderboard.last_updated, participant.user);
 
     Ok(())
@@ -26,13 +43,16 @@ pub struct UpdateLeaderboardWithParticipant<'info> {
     #[account(
         mut,
         seeds = [LEADERBOARD_SEED, league.key().as_ref()],
-        bump
+        bump = leaderboard.bump
     )]
     pub leaderboard: Account<'info, Leaderboard>,
 
-    /// CHECK: league PDA
-    pub league: UncheckedAccount<'info>,
-    /// CHECK: Your program ID
+    #[account(
+        seeds = [LEAGUE_SEED, league.creator.as_ref(), league.id.as_bytes()],
+        bump = league.bump
+    )]
+    pub league: Account<'info, League>,
+    /// CHECK: Participant account, may be delegated (validated by load_participant)
     pub participant: UncheckedAccount<'info>,
     // /// CHECK: the correct pda - this will be moved to the end in the future, meaning you can omit this unless needed
     // pub escrow: UncheckedAccount<'info>,
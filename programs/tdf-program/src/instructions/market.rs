@@ -1,27 +1,58 @@
 use anchor_lang::prelude::*;
 
+use crate::constants::MAX_TRADING_FEE_BPS;
+use crate::events::{MarketCreated, MarketDeleted, MarketUpdated};
 use crate::state::{GlobalConfig, Market, MARKET_SEED, MARKET_SPACE};
 
 pub fn create_market(
   ctx: Context<CreateMarket>,
   symbol: [u8; 16],
+  feed_id: [u8; 32],
   decimals: u8,
   max_leverage: u8,
+  max_price_age_secs: u64,
+  max_conf_bps: u16,
+  maintenance_margin_bps: u16,
+  taker_fee_bps: u16,
+  half_spread_bps: u16,
 ) -> Result<()> {
+    validate_oracle_config(max_price_age_secs, max_conf_bps)?;
+    validate_maintenance_margin(maintenance_margin_bps, max_leverage)?;
+    validate_trading_fees(taker_fee_bps, half_spread_bps)?;
+
     let now = Clock::get()?.unix_timestamp;
 
     let market = &mut ctx.accounts.market;
 
     market.symbol = symbol;
     market.price_feed = ctx.accounts.price_feed.key();
+    market.feed_id = feed_id;
     market.decimals = decimals;
     market.max_leverage = max_leverage;
+    market.max_price_age_secs = max_price_age_secs;
+    market.max_conf_bps = max_conf_bps;
+    market.maintenance_margin_bps = maintenance_margin_bps;
+    market.taker_fee_bps = taker_fee_bps;
+    market.half_spread_bps = half_spread_bps;
     market.listed_by = ctx.accounts.admin.key();
     market.created_at = now;
     market.is_active = true;
 
     market.bump = ctx.bumps.market;
 
+    emit!(MarketCreated {
+        market: market.key(),
+        symbol,
+        price_feed: market.price_feed,
+        decimals,
+        max_leverage,
+        maintenance_margin_bps,
+        taker_fee_bps,
+        half_spread_bps,
+        listed_by: market.listed_by,
+        timestamp: now,
+    });
+
     Ok(())
 }
 
@@ -31,22 +62,82 @@ pub fn update_market(
   decimals: u8,
   is_active: bool,
   max_leverage: u8,
+  max_price_age_secs: u64,
+  max_conf_bps: u16,
+  maintenance_margin_bps: u16,
+  taker_fee_bps: u16,
+  half_spread_bps: u16,
 ) -> Result<()> {
+    validate_oracle_config(max_price_age_secs, max_conf_bps)?;
+    validate_maintenance_margin(maintenance_margin_bps, max_leverage)?;
+    validate_trading_fees(taker_fee_bps, half_spread_bps)?;
+
     let market = &mut ctx.accounts.market;
     market.symbol = symbol;
     market.decimals = decimals;
     market.is_active = is_active;
     market.max_leverage = max_leverage;
+    market.max_price_age_secs = max_price_age_secs;
+    market.max_conf_bps = max_conf_bps;
+    market.maintenance_margin_bps = maintenance_margin_bps;
+    market.taker_fee_bps = taker_fee_bps;
+    market.half_spread_bps = half_spread_bps;
+
+    emit!(MarketUpdated {
+        market: market.key(),
+        symbol,
+        decimals,
+        is_active,
+        max_leverage,
+        maintenance_margin_bps,
+        taker_fee_bps,
+        half_spread_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
 
     Ok(())
 }
 
-pub fn delete_market(_ctx: Context<DeleteMarket>) -> Result<()> {
+pub fn delete_market(ctx: Context<DeleteMarket>) -> Result<()> {
     // Account will be closed automatically by Anchor's close constraint
+    emit!(MarketDeleted {
+        market: ctx.accounts.market.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
     msg!("Market account deleted");
     Ok(())
 }
 
+fn validate_oracle_config(max_price_age_secs: u64, max_conf_bps: u16) -> Result<()> {
+    require!(max_price_age_secs > 0, crate::errors::ErrorCode::InvalidOracleConfig);
+    require!(
+        max_conf_bps > 0 && max_conf_bps <= 10_000,
+        crate::errors::ErrorCode::InvalidOracleConfig
+    );
+
+    Ok(())
+}
+
+/// Maintenance margin must stay below the initial margin at max leverage
+fn validate_maintenance_margin(maintenance_margin_bps: u16, max_leverage: u8) -> Result<()> {
+    require!(
+        maintenance_margin_bps > 0
+            && (maintenance_margin_bps as u32) * (max_leverage as u32) < 10_000,
+        crate::errors::ErrorCode::InvalidMaintenanceMargin
+    );
+
+    Ok(())
+}
+
+fn validate_trading_fees(taker_fee_bps: u16, half_spread_bps: u16) -> Result<()> {
+    require!(
+        taker_fee_bps <= MAX_TRADING_FEE_BPS && half_spread_bps <= MAX_TRADING_FEE_BPS,
+        crate::errors::ErrorCode::InvalidTradingFee
+    );
+
+    Ok(())
+}
 
 #[derive(Accounts)]
 pub struct CreateMarket<'info> {
@@ -2,43 +2,122 @@ use anchor_lang::prelude::*;
 use ephemeral_rollups_sdk::anchor::commit;
 use ephemeral_rollups_sdk::ephem::{commit_accounts, commit_and_undelegate_accounts};
 
+use crate::events::PositionClosed;
 use crate::state::{Position, PARTICIPANT_SEED, POSITION_SEED, League, Market, Participant, LeagueStatus};
-use crate::utils::{get_price_and_exponent_from_pyth, calculate_notional, dir_sign, calculate_price_from_notional_and_size, calculate_unrealized_pnl};
-use crate::constants::QUOTE_DECIMALS;
+use crate::utils::{get_price_and_exponent_from_pyth, apply_half_spread, calculate_notional, calculate_taker_fee, charge_fee, dir_sign, is_buy, calculate_price_from_notional_and_size, calculate_unrealized_pnl, calculate_required_margin, calculate_liquidation_price, price_rounding, resolve_trading_fees, scale_price_to_quote_decimals, TradingFees};
 
-pub fn close_position(ctx: Context<ClosePosition>, position_seq: u64) -> Result<()> {
+pub fn close_position(ctx: Context<ClosePosition>, position_seq: u64, close_size: i64) -> Result<()> {
     let league = &ctx.accounts.league;
     let market = &ctx.accounts.market;
     let participant = &mut ctx.accounts.participant;
     let position = &mut ctx.accounts.position;
 
     require!(league.status == LeagueStatus::Active, crate::errors::ErrorCode::InvalidLeagueStatus);
+    // Positions stay closable after a market is deactivated
+    require!(league.markets.contains(&market.key()), crate::errors::ErrorCode::MarketNotInLeague);
+    require_keys_eq!(position.market, market.key(), crate::errors::ErrorCode::PositionMismatch);
     require!(position.opened_at != 0, crate::errors::ErrorCode::PositionNotOpened);
     require!(position.closed_at == 0, crate::errors::ErrorCode::PositionAlreadyClosed);
     require!(market.price_feed == ctx.accounts.price_feed.key(), crate::errors::ErrorCode::OracleMismatch);
 
-    let (current_price, current_exponent) = get_price_and_exponent_from_pyth(&ctx.accounts.price_feed)?;
-    let current_price_in_decimal = (current_price as f64 * 10_f64.powi(-current_exponent + QUOTE_DECIMALS as i32)).floor() as i64;
+    let (current_price, current_exponent) = get_price_and_exponent_from_pyth(
+        &ctx.accounts.price_feed,
+        &market.feed_id,
+        market.max_price_age_secs,
+        market.max_conf_bps,
+    )?;
+    let current_price_in_decimal = scale_price_to_quote_decimals(
+        current_price,
+        current_exponent,
+        price_rounding(is_buy(&position.direction, false)),
+    )?;
+
+    let position_key = position.key();
+    let fees = resolve_trading_fees(league, market);
+    let realized_pnl = reduce_position(
+        participant,
+        position,
+        position_key,
+        market,
+        current_price_in_decimal,
+        close_size,
+        &fees,
+    )?;
+
+    commit_accounts(
+        &ctx.accounts.user,
+        vec![&participant.to_account_info(), &position.to_account_info()],
+        &ctx.accounts.magic_context,
+        &ctx.accounts.magic_program,
+    )?;
+
+    if position.size == 0 {
+        msg!("Position closed and removed from participant, commit requested");
+    } else {
+        msg!(
+            "Position reduced by {} (realized_pnl: {}, remaining size: {}), commit requested",
+            close_size,
+            realized_pnl,
+            position.size
+        );
+    }
+
+    Ok(())
+}
+
+/// Realizes `close_size` of a position at the given oracle price.
+/// The slice fills across the league's half spread and pays its taker fee,
+/// margin and cost basis are released proportionally to the closed slice,
+/// and the position is closed and removed from the participant once fully reduced.
+/// Returns the realized PnL, before the fee.
+pub(crate) fn reduce_position(
+    participant: &mut Participant,
+    position: &mut Position,
+    position_key: Pubkey,
+    market: &Market,
+    price_in_decimal: i64,
+    close_size: i64,
+    fees: &TradingFees,
+) -> Result<i64> {
+    require!(
+        close_size > 0 && close_size <= position.size,
+        crate::errors::ErrorCode::InvalidCloseSize
+    );
 
     let prev_upnl = position.unrealized_pnl;
 
-    // Calculate realized PnL with overflow protection
-    let closing_size = position.size;
-    let closing_equity = calculate_notional(current_price_in_decimal, closing_size, market.decimals);
-    let closing_notional = calculate_notional(position.entry_price, closing_size, market.decimals);
-    let realized_pnl = (closing_equity as i64 - closing_notional as i64)
-        .checked_mul(dir_sign(position.direction.clone()) as i64)
-        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
-    let prev_locked = position
-        .notional
-        .checked_div(position.leverage as i64)
-        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
-    let new_locked = position
-        .notional
+    // Cost basis of the closed slice, the whole notional when closing in full
+    let closing_notional = if close_size == position.size {
+        position.notional
+    } else {
+        let slice = (position.notional as i128)
+            .checked_mul(close_size as i128)
+            .ok_or(crate::errors::ErrorCode::MathOverflow)?
+            / position.size as i128;
+        i64::try_from(slice).map_err(|_| crate::errors::ErrorCode::MathOverflow)?
+    };
+
+    // Calculate realized PnL at the fill price with overflow protection
+    let fill_price = apply_half_spread(
+        price_in_decimal,
+        is_buy(&position.direction, false),
+        fees.half_spread_bps,
+    )?;
+    let closing_equity = calculate_notional(fill_price, close_size, position.market_decimals)?;
+    let fee = calculate_taker_fee(closing_equity, fees.taker_fee_bps)?;
+    let realized_pnl = closing_equity
         .checked_sub(closing_notional)
         .ok_or(crate::errors::ErrorCode::MathOverflow)?
-        .checked_div(position.leverage as i64)
+        .checked_mul(dir_sign(position.direction.clone()))
         .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    let prev_locked = calculate_required_margin(position.notional, position.leverage)?;
+    let new_locked = calculate_required_margin(
+        position
+            .notional
+            .checked_sub(closing_notional)
+            .ok_or(crate::errors::ErrorCode::MathOverflow)?,
+        position.leverage,
+    )?;
     let released_margin = prev_locked
         .checked_sub(new_locked)
         .ok_or(crate::errors::ErrorCode::MathOverflow)?;
@@ -46,7 +125,7 @@ pub fn close_position(ctx: Context<ClosePosition>, position_seq: u64) -> Result<
     // Calculate closed stats with overflow protection
     position.closed_size = position
         .closed_size
-        .checked_add(closing_size)
+        .checked_add(close_size)
         .ok_or(crate::errors::ErrorCode::MathOverflow)?;
     position.closed_equity = position
         .closed_equity
@@ -55,8 +134,8 @@ pub fn close_position(ctx: Context<ClosePosition>, position_seq: u64) -> Result<
     position.closed_price = calculate_price_from_notional_and_size(
         position.closed_equity,
         position.closed_size,
-        market.decimals,
-    );
+        position.market_decimals,
+    )?;
     position.closed_pnl = position
         .closed_pnl
         .checked_add(realized_pnl)
@@ -65,7 +144,7 @@ pub fn close_position(ctx: Context<ClosePosition>, position_seq: u64) -> Result<
     // Update position with overflow protection
     position.size = position
         .size
-        .checked_sub(closing_size)
+        .checked_sub(close_size)
         .ok_or(crate::errors::ErrorCode::MathOverflow)?;
     position.notional = position
         .notional
@@ -73,11 +152,12 @@ pub fn close_position(ctx: Context<ClosePosition>, position_seq: u64) -> Result<
         .ok_or(crate::errors::ErrorCode::MathOverflow)?;
     position.unrealized_pnl = calculate_unrealized_pnl(
         position.notional,
-        current_price,
+        price_in_decimal,
         position.size,
-        market.decimals,
+        position.market_decimals,
         position.direction.clone(),
-    );
+    )?;
+    position.liquidation_price = calculate_liquidation_price(position, market.maintenance_margin_bps)?;
 
     // Update participant with overflow protection
     participant.total_volume = participant
@@ -93,6 +173,7 @@ pub fn close_position(ctx: Context<ClosePosition>, position_seq: u64) -> Result<
         .virtual_balance
         .checked_add(realized_pnl)
         .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    charge_fee(participant, fee)?;
 
     let upnl_delta = position
         .unrealized_pnl
@@ -103,21 +184,28 @@ pub fn close_position(ctx: Context<ClosePosition>, position_seq: u64) -> Result<
         .checked_add(upnl_delta)
         .ok_or(crate::errors::ErrorCode::MathOverflow)?;
 
-    // close position logic here
-    position.closed_at = Clock::get()?.unix_timestamp;
-    // remove position from participant.positions vector
-    participant.positions.retain(|p| p != &position.key());
-    
-    commit_accounts(
-        &ctx.accounts.user,
-        vec![&participant.to_account_info(), &position.to_account_info()],
-        &ctx.accounts.magic_context,
-        &ctx.accounts.magic_program,
-    )?;
+    let now = Clock::get()?.unix_timestamp;
+    if position.size == 0 {
+        position.closed_at = now;
+        // remove position from participant.positions vector
+        participant.positions.retain(|p| p != &position_key);
+        participant.reset_page();
+    }
 
-    msg!("Position closed and removed from participant, commit requested");
+    emit!(PositionClosed {
+        league: position.league,
+        user: position.user,
+        position: position_key,
+        market: position.market,
+        close_size,
+        close_price: fill_price,
+        realized_pnl,
+        fee,
+        remaining_size: position.size,
+        timestamp: now,
+    });
 
-    Ok(())
+    Ok(realized_pnl)
 }
 
 #[commit]
@@ -155,3 +243,76 @@ pub struct ClosePosition<'info> {
   /// CHECK: Price feed account (Pyth PriceUpdateV2)
   pub price_feed: AccountInfo<'info>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{Direction, MarginMode};
+    use crate::test_fixtures::{self, NOW, USD};
+
+    const NO_FEES: TradingFees = TradingFees { taker_fee_bps: 0, half_spread_bps: 0 };
+
+    #[test]
+    fn partial_close_realizes_the_closed_slice() {
+        test_fixtures::use_clock();
+        let market = test_fixtures::market();
+        // 2 SOL long at $150, 10x cross: $30 margin locked
+        let mut position = test_fixtures::position(Direction::Long, MarginMode::Cross, 150 * USD, 2_000_000);
+        let position_key = Pubkey::new_unique();
+        let mut participant = test_fixtures::participant(10_000 * USD);
+        participant.used_margin = 30 * USD;
+        participant.positions.push(position_key);
+
+        let realized_pnl =
+            reduce_position(&mut participant, &mut position, position_key, &market, 160 * USD, 1_000_000, &NO_FEES)
+                .unwrap();
+
+        assert_eq!(realized_pnl, 10 * USD);
+        assert_eq!(position.size, 1_000_000);
+        assert_eq!(position.notional, 150 * USD);
+        assert_eq!(position.unrealized_pnl, 10 * USD);
+        assert_eq!(position.closed_size, 1_000_000);
+        assert_eq!(position.closed_price, 160 * USD);
+        assert_eq!(position.closed_at, 0);
+        assert_eq!(participant.virtual_balance, 10_010 * USD);
+        assert_eq!(participant.unrealized_pnl, 10 * USD);
+        assert_eq!(participant.used_margin, 15 * USD);
+        assert_eq!(participant.positions, vec![position_key]);
+
+        // Closing the rest closes the position and removes it from the participant
+        reduce_position(&mut participant, &mut position, position_key, &market, 160 * USD, 1_000_000, &NO_FEES)
+            .unwrap();
+
+        assert_eq!(position.size, 0);
+        assert_eq!(position.closed_pnl, 20 * USD);
+        assert_eq!(position.closed_at, NOW);
+        assert_eq!(participant.virtual_balance, 10_020 * USD);
+        assert_eq!(participant.unrealized_pnl, 0);
+        assert_eq!(participant.used_margin, 0);
+        assert!(participant.positions.is_empty());
+    }
+
+    #[test]
+    fn close_size_must_be_within_the_position() {
+        test_fixtures::use_clock();
+        let market = test_fixtures::market();
+        let mut position = test_fixtures::position(Direction::Short, MarginMode::Cross, 150 * USD, 2_000_000);
+        let mut participant = test_fixtures::participant(10_000 * USD);
+
+        for close_size in [0, 2_000_001] {
+            assert_eq!(
+                reduce_position(
+                    &mut participant,
+                    &mut position,
+                    Pubkey::new_unique(),
+                    &market,
+                    150 * USD,
+                    close_size,
+                    &NO_FEES,
+                )
+                .unwrap_err(),
+                crate::errors::ErrorCode::InvalidCloseSize.into()
+            );
+        }
+    }
+}
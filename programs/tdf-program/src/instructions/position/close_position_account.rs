@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+
+use crate::events::PositionArchived;
+use crate::state::{Position, POSITION_SEED};
+
+/// Closes a finished position on the base layer and refunds its rent to the user.
+/// The position must be undelegated first (see `undelegate_position`),
+/// and its final stats are archived in a `PositionArchived` event.
+#[allow(unused_variables)]
+pub fn close_position_account(ctx: Context<ClosePositionAccount>, league: Pubkey, position_seq: u64) -> Result<()> {
+    let position = &ctx.accounts.position;
+
+    // Closed positions, or positions that were never opened
+    require!(
+        position.opened_at == 0 || position.closed_at != 0,
+        crate::errors::ErrorCode::PositionStillOpen
+    );
+
+    emit!(PositionArchived {
+        league: position.league,
+        user: position.user,
+        position: position.key(),
+        market: position.market,
+        seq_num: position.seq_num,
+        direction: position.direction.clone(),
+        margin_mode: position.margin_mode.clone(),
+        leverage: position.leverage,
+        entry_price: position.entry_price,
+        entry_size: position.entry_size,
+        closed_size: position.closed_size,
+        closed_price: position.closed_price,
+        closed_pnl: position.closed_pnl,
+        liquidation_penalty: position.liquidation_penalty,
+        opened_at: position.opened_at,
+        closed_at: position.closed_at,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!("Position account {} closed, rent returned to user", position.key());
+
+    Ok(())
+}
+
+/// Close Position Account (on base layer)
+/// - user reclaims the rent of an undelegated, finished position
+#[derive(Accounts)]
+#[instruction(league: Pubkey, position_seq: u64)]
+pub struct ClosePositionAccount<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [
+            POSITION_SEED,
+            league.as_ref(),
+            user.key().as_ref(),
+            position_seq.to_le_bytes().as_ref()
+        ],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+}
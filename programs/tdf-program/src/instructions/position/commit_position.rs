@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 use ephemeral_rollups_sdk::anchor::commit;
-use ephemeral_rollups_sdk::ephem::commit_accounts;
+use ephemeral_rollups_sdk::ephem::{commit_accounts, commit_and_undelegate_accounts};
 
 use crate::state::{Position, POSITION_SEED};
 
@@ -16,6 +16,29 @@ pub fn commit_position(ctx: Context<CommitPosition>, league: Pubkey, user: Pubke
     Ok(())
 }
 
+/// Commits and undelegates a position that is done trading, so it can be closed on the base layer.
+/// Anyone can undelegate a closed position; an unopened one may back a pending limit order,
+/// so only its owner can undelegate it.
+#[allow(unused_variables)]
+pub fn undelegate_position(ctx: Context<CommitPosition>, league: Pubkey, user: Pubkey, position_seq: u64) -> Result<()> {
+    let position = &ctx.accounts.position;
+    if position.closed_at == 0 {
+        require!(position.opened_at == 0, crate::errors::ErrorCode::PositionStillOpen);
+        require_keys_eq!(ctx.accounts.payer.key(), position.user, crate::errors::ErrorCode::PositionMismatch);
+    }
+
+    commit_and_undelegate_accounts(
+        &ctx.accounts.payer,
+        vec![&position.to_account_info()],
+        &ctx.accounts.magic_context,
+        &ctx.accounts.magic_program,
+    )?;
+
+    msg!("Undelegated position {}", position_seq);
+
+    Ok(())
+}
+
 #[commit]
 #[derive(Accounts)]
 #[instruction(league: Pubkey, user: Pubkey, position_seq: u64)]
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+
+use crate::state::{participant_space, Participant, MAX_POSITIONS_LIMIT, PARTICIPANT_SEED};
+
+/// Grows the participant's position capacity (on base layer).
+/// The participant must be undelegated, since delegated accounts can't be resized.
+/// Capacity stops at `MAX_POSITIONS_LIMIT`: `update_participant` and `liquidate_participant` take
+/// every position in one transaction, so a keeper can always liquidate the whole account at once.
+#[allow(unused_variables)]
+pub fn expand_participant_positions(
+    ctx: Context<ExpandParticipantPositions>,
+    league: Pubkey,
+    new_max_positions: u16,
+) -> Result<()> {
+    let participant = &mut ctx.accounts.participant;
+
+    require!(
+        new_max_positions > participant.max_positions && new_max_positions <= MAX_POSITIONS_LIMIT,
+        crate::errors::ErrorCode::InvalidMaxPositions
+    );
+
+    // Top up rent for the new size, paid by the user
+    let participant_info = participant.to_account_info();
+    let new_space = participant_space(new_max_positions);
+    let rent_due = Rent::get()?
+        .minimum_balance(new_space)
+        .saturating_sub(participant_info.lamports());
+    if rent_due > 0 {
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: participant_info.clone(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    participant_info.resize(new_space)?;
+
+    participant.max_positions = new_max_positions;
+
+    msg!("Participant positions expanded to {}", new_max_positions);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(league: Pubkey, new_max_positions: u16)]
+pub struct ExpandParticipantPositions<'info> {
+    #[account(
+        mut,
+        seeds = [PARTICIPANT_SEED, league.as_ref(), user.key().as_ref()],
+        bump = participant.bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,267 @@
+/// CAUTION: This instruction is applied only to ER.
+/// Base Layer's price feed is not updated in realtime.
+use anchor_lang::prelude::*;
+
+use crate::events::PositionIncreased;
+use crate::state::{League, LeagueStatus, Market, Participant, Position, PARTICIPANT_SEED, POSITION_SEED};
+use crate::utils::{
+    get_price_and_exponent_from_pyth, apply_half_spread, calculate_notional, calculate_price_from_notional_and_size,
+    calculate_liquidation_price, calculate_required_margin, calculate_taker_fee, calculate_unrealized_pnl,
+    charge_fee, is_buy, price_rounding, resolve_trading_fees, scale_price_to_quote_decimals, TradingFees,
+};
+
+#[allow(unused_variables)]
+pub fn increase_position(ctx: Context<IncreasePosition>, position_seq: u64, size: i64) -> Result<()> {
+    let league = &ctx.accounts.league;
+    let market = &ctx.accounts.market;
+    let participant = &mut ctx.accounts.participant;
+    let position = &mut ctx.accounts.position;
+
+    require!(league.status == LeagueStatus::Active, crate::errors::ErrorCode::InvalidLeagueStatus);
+    require!(league.markets.contains(&market.key()), crate::errors::ErrorCode::MarketNotInLeague);
+    require!(market.is_active, crate::errors::ErrorCode::MarketNotActive);
+    require_keys_eq!(position.market, market.key(), crate::errors::ErrorCode::PositionMismatch);
+    require!(position.opened_at != 0, crate::errors::ErrorCode::PositionNotOpened);
+    require!(position.closed_at == 0, crate::errors::ErrorCode::PositionAlreadyClosed);
+    require!(position.leverage <= league.max_leverage, crate::errors::ErrorCode::InvalidLeverage);
+    require!(position.leverage <= market.max_leverage, crate::errors::ErrorCode::InvalidLeverage);
+    require!(market.price_feed == ctx.accounts.price_feed.key(), crate::errors::ErrorCode::OracleMismatch);
+
+    let (current_price, current_exponent) = get_price_and_exponent_from_pyth(
+        &ctx.accounts.price_feed,
+        &market.feed_id,
+        market.max_price_age_secs,
+        market.max_conf_bps,
+    )?;
+    let current_price_in_decimal = scale_price_to_quote_decimals(
+        current_price,
+        current_exponent,
+        price_rounding(is_buy(&position.direction, true)),
+    )?;
+
+    let position_key = position.key();
+    let fees = resolve_trading_fees(league, market);
+    add_to_position(participant, position, position_key, market, current_price_in_decimal, size, &fees)?;
+
+    msg!(
+        "Position increased by {} at oracle price {}, new entry price {}",
+        size,
+        current_price_in_decimal,
+        position.entry_price
+    );
+
+    Ok(())
+}
+
+/// Adds `size` to an open position at the given oracle price, filled across the league's half spread.
+/// Entry price becomes the notional-weighted average, and the extra margin at the position's
+/// leverage plus the taker fee must fit in the participant's available balance.
+pub(crate) fn add_to_position(
+    participant: &mut Participant,
+    position: &mut Position,
+    position_key: Pubkey,
+    market: &Market,
+    price_in_decimal: i64,
+    size: i64,
+    fees: &TradingFees,
+) -> Result<()> {
+    require!(size > 0, crate::errors::ErrorCode::InvalidPositionSize);
+
+    let prev_upnl = position.unrealized_pnl;
+
+    let fill_price = apply_half_spread(
+        price_in_decimal,
+        is_buy(&position.direction, true),
+        fees.half_spread_bps,
+    )?;
+    let added_notional = calculate_notional(fill_price, size, position.market_decimals)?;
+    let fee = calculate_taker_fee(added_notional, fees.taker_fee_bps)?;
+    let new_notional = position
+        .notional
+        .checked_add(added_notional)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    let new_size = position
+        .size
+        .checked_add(size)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    let additional_margin = calculate_required_margin(new_notional, position.leverage)?
+        .checked_sub(calculate_required_margin(position.notional, position.leverage)?)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    require!(
+        participant.available_balance()
+            >= additional_margin
+                .checked_add(fee)
+                .ok_or(crate::errors::ErrorCode::MathOverflow)?,
+        crate::errors::ErrorCode::InsufficientBalance
+    );
+
+    // Update position with overflow protection
+    position.entry_size = position
+        .entry_size
+        .checked_add(size)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    position.size = new_size;
+    position.notional = new_notional;
+    position.entry_price = calculate_price_from_notional_and_size(
+        new_notional,
+        new_size,
+        position.market_decimals,
+    )?;
+    position.unrealized_pnl = calculate_unrealized_pnl(
+        new_notional,
+        price_in_decimal,
+        new_size,
+        position.market_decimals,
+        position.direction.clone(),
+    )?;
+    position.liquidation_price = calculate_liquidation_price(position, market.maintenance_margin_bps)?;
+
+    // Update participant with overflow protection
+    participant.total_volume = participant
+        .total_volume
+        .checked_add(added_notional)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    participant.used_margin = participant
+        .used_margin
+        .checked_add(additional_margin)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    charge_fee(participant, fee)?;
+
+    let upnl_delta = position
+        .unrealized_pnl
+        .checked_sub(prev_upnl)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    participant.unrealized_pnl = participant
+        .unrealized_pnl
+        .checked_add(upnl_delta)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    emit!(PositionIncreased {
+        league: position.league,
+        user: position.user,
+        position: position_key,
+        market: position.market,
+        added_size: size,
+        fill_price,
+        entry_price: position.entry_price,
+        size: position.size,
+        fee,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Increase Position (on ER)
+/// - user adds size to an open position at the current oracle price
+#[derive(Accounts)]
+#[instruction(position_seq: u64)]
+pub struct IncreasePosition<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            POSITION_SEED,
+            league.key().as_ref(),
+            user.key().as_ref(),
+            position_seq.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        mut,
+        seeds = [
+            PARTICIPANT_SEED,
+            league.key().as_ref(),
+            user.key().as_ref()
+        ],
+        bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    pub league: Account<'info, League>,
+    pub market: Account<'info, Market>,
+    /// CHECK: Price feed account (Pyth PriceUpdateV2)
+    pub price_feed: AccountInfo<'info>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{Direction, MarginMode};
+    use crate::test_fixtures::{self, USD};
+
+    const NO_FEES: TradingFees = TradingFees { taker_fee_bps: 0, half_spread_bps: 0 };
+
+    #[test]
+    fn add_to_position_averages_the_entry_price() {
+        test_fixtures::use_clock();
+        let market = test_fixtures::market();
+        // 2 SOL long at $150, 10x cross: $30 margin locked
+        let mut position = test_fixtures::position(Direction::Long, MarginMode::Cross, 150 * USD, 2_000_000);
+        let mut participant = test_fixtures::participant(10_000 * USD);
+        participant.used_margin = 30 * USD;
+
+        add_to_position(&mut participant, &mut position, Pubkey::new_unique(), &market, 180 * USD, 1_000_000, &NO_FEES)
+            .unwrap();
+
+        // ($300 + $180) / 3 SOL
+        assert_eq!(position.size, 3_000_000);
+        assert_eq!(position.entry_size, 3_000_000);
+        assert_eq!(position.notional, 480 * USD);
+        assert_eq!(position.entry_price, 160 * USD);
+        assert_eq!(position.unrealized_pnl, 60 * USD);
+
+        assert_eq!(participant.used_margin, 48 * USD);
+        assert_eq!(participant.unrealized_pnl, 60 * USD);
+        assert_eq!(participant.total_volume, 180 * USD);
+        assert_eq!(participant.virtual_balance, 10_000 * USD);
+    }
+
+    #[test]
+    fn add_to_position_fills_across_the_spread_and_pays_the_fee() {
+        test_fixtures::use_clock();
+        let market = test_fixtures::market();
+        let fees = TradingFees { taker_fee_bps: 5, half_spread_bps: 5 };
+        let mut position = test_fixtures::position(Direction::Long, MarginMode::Cross, 150 * USD, 2_000_000);
+        let mut participant = test_fixtures::participant(10_000 * USD);
+        participant.used_margin = 30 * USD;
+
+        add_to_position(&mut participant, &mut position, Pubkey::new_unique(), &market, 150 * USD, 1_000_000, &fees)
+            .unwrap();
+
+        // 1 SOL bought at $150.075, the spread is an immediate loss at the oracle price
+        assert_eq!(position.notional, 450_075_000);
+        assert_eq!(position.entry_price, 150_025_000);
+        assert_eq!(position.unrealized_pnl, -75_000);
+        assert_eq!(participant.used_margin, 45_007_500);
+        // 5 bps of $150.075, rounded up
+        assert_eq!(participant.total_fees, 75_038);
+        assert_eq!(participant.virtual_balance, 10_000 * USD - 75_038);
+    }
+
+    #[test]
+    fn add_to_position_requires_size_and_available_balance() {
+        test_fixtures::use_clock();
+        let market = test_fixtures::market();
+        let mut position = test_fixtures::position(Direction::Short, MarginMode::Isolated, 150 * USD, 2_000_000);
+        // $15 margin is needed for the extra SOL at 10x
+        let mut participant = test_fixtures::participant(14 * USD);
+
+        assert_eq!(
+            add_to_position(&mut participant, &mut position, Pubkey::new_unique(), &market, 150 * USD, 1_000_000, &NO_FEES)
+                .unwrap_err(),
+            crate::errors::ErrorCode::InsufficientBalance.into()
+        );
+        assert_eq!(
+            add_to_position(&mut participant, &mut position, Pubkey::new_unique(), &market, 150 * USD, 0, &NO_FEES)
+                .unwrap_err(),
+            crate::errors::ErrorCode::InvalidPositionSize.into()
+        );
+    }
+}
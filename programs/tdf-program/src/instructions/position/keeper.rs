@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+use ephemeral_rollups_sdk::anchor::{commit, delegate};
+use ephemeral_rollups_sdk::cpi::DelegateConfig;
+use ephemeral_rollups_sdk::ephem::commit_and_undelegate_accounts;
+
+use crate::state::{Keeper, KEEPER_SEED, KEEPER_SPACE};
+
+/// Registers a keeper (on base layer).
+/// Anyone can register, the keeper account collects liquidation rewards across leagues.
+pub fn init_keeper(ctx: Context<InitKeeper>) -> Result<()> {
+    let keeper = &mut ctx.accounts.keeper;
+    keeper.authority = ctx.accounts.authority.key();
+    keeper.liquidations = 0;
+    keeper.reward_points = 0;
+    keeper.bump = ctx.bumps.keeper;
+
+    msg!("Keeper registered: {:?}", keeper.authority);
+
+    Ok(())
+}
+
+/// Delegates the keeper account so liquidations on ER can credit it.
+pub fn delegate_keeper(ctx: Context<DelegateKeeper>) -> Result<()> {
+    let authority = &ctx.accounts.authority;
+    ctx.accounts.delegate_keeper(
+        authority,
+        &[KEEPER_SEED, authority.key().as_ref()],
+        DelegateConfig {
+            validator: ctx.remaining_accounts.first().map(|acc| acc.key()),
+            ..Default::default()
+        },
+    )?;
+
+    msg!("Delegated keeper to validator: {:?}", ctx.remaining_accounts.first().map(|acc| acc.key()));
+
+    Ok(())
+}
+
+/// Commits the keeper's reward points accrued on the ER and returns the account to the base layer.
+pub fn undelegate_keeper(ctx: Context<UndelegateKeeper>) -> Result<()> {
+    let keeper = &ctx.accounts.keeper;
+
+    commit_and_undelegate_accounts(
+        &ctx.accounts.authority,
+        vec![&keeper.to_account_info()],
+        &ctx.accounts.magic_context,
+        &ctx.accounts.magic_program,
+    )?;
+
+    msg!(
+        "Undelegated keeper {:?} (liquidations: {}, reward points: {})",
+        keeper.authority,
+        keeper.liquidations,
+        keeper.reward_points
+    );
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitKeeper<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = KEEPER_SPACE,
+        seeds = [KEEPER_SEED, authority.key().as_ref()],
+        bump
+    )]
+    pub keeper: Account<'info, Keeper>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[delegate]
+#[derive(Accounts)]
+pub struct DelegateKeeper<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Keeper account
+    #[account(mut, del)]
+    pub keeper: AccountInfo<'info>,
+}
+
+#[commit]
+#[derive(Accounts)]
+pub struct UndelegateKeeper<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [KEEPER_SEED, authority.key().as_ref()],
+        bump = keeper.bump
+    )]
+    pub keeper: Account<'info, Keeper>,
+}
@@ -0,0 +1,621 @@
+use anchor_lang::prelude::*;
+use ephemeral_rollups_sdk::anchor::{commit, delegate};
+use ephemeral_rollups_sdk::cpi::DelegateConfig;
+use ephemeral_rollups_sdk::ephem::commit_accounts;
+
+use crate::events::{OrderCancelled, OrderFilled, OrderPlaced};
+use crate::instructions::fill_position;
+use crate::state::{
+    Direction, League, LeagueStatus, MarginMode, Market, Order, Participant, Position, PositionMode,
+    ORDER_SEED, ORDER_SPACE, PARTICIPANT_SEED, POSITION_SEED,
+};
+use crate::utils::{
+    get_price_and_exponent_from_pyth, calculate_notional, calculate_required_margin,
+    calculate_taker_fee, is_buy, price_rounding, resolve_trading_fees, scale_price_to_quote_decimals,
+    TradingFees,
+};
+
+/// Initialize Order just for delegation
+pub fn init_order(ctx: Context<InitOrder>, league: Pubkey, order_seq: u64) -> Result<()> {
+    let order = &mut ctx.accounts.order;
+    order.league = league;
+    order.user = ctx.accounts.user.key();
+    order.seq_num = order_seq;
+
+    order.bump = ctx.bumps.order;
+
+    Ok(())
+}
+
+pub fn delegate_order(ctx: Context<DelegateOrder>, order_seq: u64) -> Result<()> {
+    ctx.accounts.delegate_order(
+        &ctx.accounts.user,
+        &[
+            ORDER_SEED,
+            ctx.accounts.league.key().as_ref(),
+            ctx.accounts.user.key().as_ref(),
+            order_seq.to_le_bytes().as_ref()
+        ],
+        DelegateConfig {
+          ..Default::default()
+        },
+    )?;
+
+    Ok(())
+}
+
+/// Places a limit order on the ER and reserves its margin at the limit price.
+/// The order takes the participant's current position sequence, so the unopened
+/// position with the same sequence is the one opened when the order fills.
+#[allow(unused_variables)]
+pub fn place_order(
+    ctx: Context<PlaceOrder>,
+    order_seq: u64,
+    direction: Direction,
+    size: i64,
+    leverage: u8,
+    margin_mode: MarginMode,
+    limit_price: i64,
+    expiry_ts: i64,
+) -> Result<()> {
+    let league = &ctx.accounts.league;
+    let market = &ctx.accounts.market;
+    let participant = &mut ctx.accounts.participant;
+    let order = &mut ctx.accounts.order;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(league.status == LeagueStatus::Active, crate::errors::ErrorCode::InvalidLeagueStatus);
+    require!(league.markets.contains(&market.key()), crate::errors::ErrorCode::MarketNotInLeague);
+    require!(market.is_active, crate::errors::ErrorCode::MarketNotActive);
+    // Fills open a separate position, which one-way leagues don't allow
+    require!(league.position_mode == PositionMode::Hedge, crate::errors::ErrorCode::InvalidPositionMode);
+    require!(size > 0, crate::errors::ErrorCode::InvalidPositionSize);
+    require!(leverage > 0, crate::errors::ErrorCode::InvalidLeverage);
+    require!(leverage <= league.max_leverage, crate::errors::ErrorCode::InvalidLeverage);
+    require!(leverage <= market.max_leverage, crate::errors::ErrorCode::InvalidLeverage);
+    require!(limit_price > 0, crate::errors::ErrorCode::InvalidLimitPrice);
+    require!(expiry_ts == 0 || expiry_ts > now, crate::errors::ErrorCode::InvalidOrderExpiry);
+    require!(order.placed_at == 0, crate::errors::ErrorCode::OrderAlreadyPlaced);
+    require!(
+        order_seq == participant.current_position_seq,
+        crate::errors::ErrorCode::InvalidPositionSequence
+    );
+
+    // Fill out order account
+    order.market = market.key();
+    order.direction = direction;
+    order.size = size;
+    order.leverage = leverage;
+    order.margin_mode = margin_mode;
+    order.limit_price = limit_price;
+    order.expiry_ts = expiry_ts;
+    order.placed_at = now;
+
+    // Reserve margin and take the position sequence for the order
+    reserve_order_margin(participant, order, market, &resolve_trading_fees(league, market))?;
+    let reserved_margin = order.reserved_margin;
+    participant.current_position_seq = participant
+        .current_position_seq
+        .checked_add(1)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    // The next init_unopened_position checks the sequence on the base layer copy of the participant
+    participant.exit(&crate::ID)?;
+    order.exit(&crate::ID)?;
+    commit_accounts(
+        &ctx.accounts.user,
+        vec![&participant.to_account_info(), &order.to_account_info()],
+        &ctx.accounts.magic_context,
+        &ctx.accounts.magic_program,
+    )?;
+
+    msg!(
+        "Limit order placed: size {} at limit price {} (reserved margin: {}), commit requested",
+        size,
+        limit_price,
+        reserved_margin
+    );
+
+    emit!(OrderPlaced {
+        league: order.league,
+        user: order.user,
+        order: order.key(),
+        market: order.market,
+        seq_num: order.seq_num,
+        direction: order.direction.clone(),
+        margin_mode: order.margin_mode.clone(),
+        size,
+        leverage,
+        limit_price,
+        reserved_margin,
+        expiry_ts,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Opens the order's position at the limit price once the oracle price crosses it.
+/// Any keeper can fill it: the order already fixes the size, leverage and price, and the
+/// position was initialized and delegated by the user when the order was placed.
+#[allow(unused_variables)]
+pub fn fill_order(ctx: Context<FillOrder>, user: Pubkey, order_seq: u64) -> Result<()> {
+    let league = &ctx.accounts.league;
+    let market = &ctx.accounts.market;
+    let participant = &mut ctx.accounts.participant;
+    let position = &mut ctx.accounts.position;
+    let order = &mut ctx.accounts.order;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(league.status == LeagueStatus::Active, crate::errors::ErrorCode::InvalidLeagueStatus);
+    require!(league.markets.contains(&market.key()), crate::errors::ErrorCode::MarketNotInLeague);
+    require!(market.is_active, crate::errors::ErrorCode::MarketNotActive);
+    require!(is_order_open(order), crate::errors::ErrorCode::OrderNotOpen);
+    // The reservation was released by a liquidation, the order is void
+    require!(is_reservation_held(participant, order), crate::errors::ErrorCode::OrderNotOpen);
+    require!(order.expiry_ts == 0 || now < order.expiry_ts, crate::errors::ErrorCode::OrderExpired);
+    require_keys_eq!(order.market, market.key(), crate::errors::ErrorCode::PositionMismatch);
+    require_keys_eq!(position.market, market.key(), crate::errors::ErrorCode::PositionMismatch);
+    require!(market.price_feed == ctx.accounts.price_feed.key(), crate::errors::ErrorCode::OracleMismatch);
+    require!(
+        participant.positions.len() < participant.max_positions as usize,
+        crate::errors::ErrorCode::MaxOpenPositionExceeded
+    );
+    require!(position.opened_at == 0, crate::errors::ErrorCode::PositionAlreadyOpened);
+
+    let (current_price, current_exponent) = get_price_and_exponent_from_pyth(
+        &ctx.accounts.price_feed,
+        &market.feed_id,
+        market.max_price_age_secs,
+        market.max_conf_bps,
+    )?;
+    // Rounded against the order, so it only fills once the oracle has really crossed the limit
+    let current_price_in_decimal = scale_price_to_quote_decimals(
+        current_price,
+        current_exponent,
+        price_rounding(is_buy(&order.direction, true)),
+    )?;
+
+    require!(
+        is_limit_crossed(order, current_price_in_decimal),
+        crate::errors::ErrorCode::LimitPriceNotReached
+    );
+
+    // Release the reservation, the position locks its own margin at the limit price.
+    // Limit fills don't cross the spread, only the taker fee applies.
+    release_reserved_margin(participant, order)?;
+    let fees = resolve_trading_fees(league, market);
+    fill_position(
+        participant,
+        position,
+        market,
+        order.direction.clone(),
+        order.size,
+        order.leverage,
+        order.margin_mode.clone(),
+        order.limit_price,
+        &fees,
+    )?;
+    order.filled_at = now;
+
+    commit_accounts(
+        &ctx.accounts.keeper,
+        vec![
+            &participant.to_account_info(),
+            &position.to_account_info(),
+            &order.to_account_info(),
+        ],
+        &ctx.accounts.magic_context,
+        &ctx.accounts.magic_program,
+    )?;
+
+    msg!(
+        "Limit order filled at price {} (oracle price: {}), commit requested",
+        order.limit_price,
+        current_price_in_decimal
+    );
+
+    emit!(OrderFilled {
+        league: order.league,
+        user: order.user,
+        order: order.key(),
+        position: position.key(),
+        market: order.market,
+        keeper: ctx.accounts.keeper.key(),
+        size: order.size,
+        limit_price: order.limit_price,
+        oracle_price: current_price_in_decimal,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Cancels an open order and releases its reserved margin.
+/// The owner can cancel at any time, anyone else only once the order has expired.
+#[allow(unused_variables)]
+pub fn cancel_order(ctx: Context<CancelOrder>, user: Pubkey, order_seq: u64) -> Result<()> {
+    let participant = &mut ctx.accounts.participant;
+    let order = &mut ctx.accounts.order;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(is_order_open(order), crate::errors::ErrorCode::OrderNotOpen);
+    if ctx.accounts.payer.key() != user {
+        require!(
+            order.expiry_ts != 0 && now >= order.expiry_ts,
+            crate::errors::ErrorCode::OrderNotExpired
+        );
+    }
+
+    // Already released if the participant was liquidated since placing the order
+    let released_margin = if is_reservation_held(participant, order) {
+        release_reserved_margin(participant, order)?;
+        order.reserved_margin
+    } else {
+        0
+    };
+    order.cancelled_at = now;
+
+    commit_accounts(
+        &ctx.accounts.payer,
+        vec![&participant.to_account_info(), &order.to_account_info()],
+        &ctx.accounts.magic_context,
+        &ctx.accounts.magic_program,
+    )?;
+
+    msg!("Limit order cancelled, reserved margin {} released", released_margin);
+
+    emit!(OrderCancelled {
+        league: order.league,
+        user: order.user,
+        order: order.key(),
+        cancelled_by: ctx.accounts.payer.key(),
+        released_margin,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Reserves the order's margin at its limit price.
+/// The taker fee is charged at fill, but must be affordable when placing.
+fn reserve_order_margin(
+    participant: &mut Participant,
+    order: &mut Order,
+    market: &Market,
+    fees: &TradingFees,
+) -> Result<()> {
+    let notional = calculate_notional(order.limit_price, order.size, market.decimals)?;
+    let reserved_margin = calculate_required_margin(notional, order.leverage)?;
+    let fee = calculate_taker_fee(notional, fees.taker_fee_bps)?;
+    require!(
+        participant.available_balance()
+            >= reserved_margin
+                .checked_add(fee)
+                .ok_or(crate::errors::ErrorCode::MathOverflow)?,
+        crate::errors::ErrorCode::InsufficientBalance
+    );
+
+    order.reserved_margin = reserved_margin;
+    order.reservation_epoch = participant.reservation_epoch;
+    participant.reserved_margin = participant
+        .reserved_margin
+        .checked_add(reserved_margin)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    participant.used_margin = participant
+        .used_margin
+        .checked_add(reserved_margin)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Whether the oracle price has reached the order's limit price.
+fn is_limit_crossed(order: &Order, price: i64) -> bool {
+    match order.direction {
+        Direction::Long => price <= order.limit_price,
+        Direction::Short => price >= order.limit_price,
+    }
+}
+
+fn is_order_open(order: &Order) -> bool {
+    order.placed_at != 0 && order.filled_at == 0 && order.cancelled_at == 0
+}
+
+fn is_reservation_held(participant: &Participant, order: &Order) -> bool {
+    order.reservation_epoch == participant.reservation_epoch
+}
+
+/// Releases the reservations of all the participant's open orders at once, when it is liquidated.
+/// Those orders can no longer fill and are cancelled without releasing again.
+pub(crate) fn release_all_reserved_margin(participant: &mut Participant) -> Result<()> {
+    participant.used_margin = participant
+        .used_margin
+        .checked_sub(participant.reserved_margin)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    participant.reserved_margin = 0;
+    participant.reservation_epoch = participant
+        .reservation_epoch
+        .checked_add(1)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    Ok(())
+}
+
+fn release_reserved_margin(participant: &mut Participant, order: &Order) -> Result<()> {
+    participant.reserved_margin = participant
+        .reserved_margin
+        .checked_sub(order.reserved_margin)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    participant.used_margin = participant
+        .used_margin
+        .checked_sub(order.reserved_margin)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(league: Pubkey, order_seq: u64)]
+pub struct InitOrder<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = ORDER_SPACE,
+        seeds = [
+            ORDER_SEED,
+            league.as_ref(),
+            user.key().as_ref(),
+            order_seq.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub order: Account<'info, Order>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[delegate]
+#[derive(Accounts)]
+pub struct DelegateOrder<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub league: Account<'info, League>,
+
+    /// CHECK: Order account
+    #[account(mut, del)]
+    pub order: AccountInfo<'info>,
+}
+
+/// Place Order (on ER)
+/// - user places a limit order, reserving margin at the limit price
+#[commit]
+#[derive(Accounts)]
+#[instruction(order_seq: u64)]
+pub struct PlaceOrder<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            ORDER_SEED,
+            league.key().as_ref(),
+            user.key().as_ref(),
+            order_seq.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub order: Account<'info, Order>,
+
+    #[account(
+        mut,
+        seeds = [
+            PARTICIPANT_SEED,
+            league.key().as_ref(),
+            user.key().as_ref()
+        ],
+        bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    pub league: Account<'info, League>,
+    pub market: Account<'info, Market>,
+}
+
+/// Fill Order (on ER)
+/// - keeper opens the order's position once the oracle price crosses the limit price
+#[commit]
+#[derive(Accounts)]
+#[instruction(user: Pubkey, order_seq: u64)]
+pub struct FillOrder<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            ORDER_SEED,
+            league.key().as_ref(),
+            user.as_ref(),
+            order_seq.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub order: Account<'info, Order>,
+
+    #[account(
+        mut,
+        seeds = [
+            POSITION_SEED,
+            league.key().as_ref(),
+            user.as_ref(),
+            order_seq.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        mut,
+        seeds = [
+            PARTICIPANT_SEED,
+            league.key().as_ref(),
+            user.as_ref()
+        ],
+        bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    pub league: Account<'info, League>,
+    pub market: Account<'info, Market>,
+    /// CHECK: Price feed account (Pyth PriceUpdateV2)
+    pub price_feed: AccountInfo<'info>,
+}
+
+/// Cancel Order (on ER)
+/// - user cancels an open limit order, or anyone cancels an expired one, releasing its reserved margin
+#[commit]
+#[derive(Accounts)]
+#[instruction(user: Pubkey, order_seq: u64)]
+pub struct CancelOrder<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            ORDER_SEED,
+            league.key().as_ref(),
+            user.as_ref(),
+            order_seq.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub order: Account<'info, Order>,
+
+    #[account(
+        mut,
+        seeds = [
+            PARTICIPANT_SEED,
+            league.key().as_ref(),
+            user.as_ref()
+        ],
+        bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    pub league: Account<'info, League>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorCode;
+    use crate::test_fixtures::{self, TestAccount, USD};
+
+    const FEES: TradingFees = TradingFees { taker_fee_bps: 5, half_spread_bps: 5 };
+
+    /// $1,000 balance with a 2 SOL long limit order at $140 reserving its $28 margin.
+    fn placed_long() -> (Participant, Order) {
+        let mut participant = test_fixtures::participant(1_000 * USD);
+        let mut order = test_fixtures::order(Direction::Long, 140 * USD, 2_000_000);
+        reserve_order_margin(&mut participant, &mut order, &test_fixtures::market(), &FEES).unwrap();
+        (participant, order)
+    }
+
+    #[test]
+    fn placing_reserves_margin_at_the_limit_price() {
+        let (participant, order) = placed_long();
+
+        assert_eq!(order.reserved_margin, 28 * USD);
+        assert_eq!(participant.reserved_margin, 28 * USD);
+        assert_eq!(participant.used_margin, 28 * USD);
+        assert!(is_order_open(&order) && is_reservation_held(&participant, &order));
+    }
+
+    #[test]
+    fn placing_requires_margin_and_the_fill_fee() {
+        // $28 margin plus the $0.14 fee
+        let mut participant = test_fixtures::participant(28 * USD);
+        let mut order = test_fixtures::order(Direction::Long, 140 * USD, 2_000_000);
+
+        assert_eq!(
+            reserve_order_margin(&mut participant, &mut order, &test_fixtures::market(), &FEES).unwrap_err(),
+            ErrorCode::InsufficientBalance.into()
+        );
+    }
+
+    #[test]
+    fn orders_fill_once_the_oracle_crosses_the_limit() {
+        let long = test_fixtures::order(Direction::Long, 140 * USD, 2_000_000);
+        assert!(!is_limit_crossed(&long, 140 * USD + 1));
+        assert!(is_limit_crossed(&long, 140 * USD));
+
+        let short = test_fixtures::order(Direction::Short, 160 * USD, 2_000_000);
+        assert!(!is_limit_crossed(&short, 160 * USD - 1));
+        assert!(is_limit_crossed(&short, 160 * USD));
+    }
+
+    #[test]
+    fn filling_moves_the_reservation_into_the_position() {
+        test_fixtures::use_clock();
+        let market = test_fixtures::market();
+        let (mut participant, order) = placed_long();
+        let unopened = test_fixtures::position(Direction::Long, MarginMode::Cross, 0, 0);
+        let mut position_account = TestAccount::new(Pubkey::new_unique(), crate::ID, &unopened);
+        let position_info = position_account.info();
+        let mut position = Account::<Position>::try_from(&position_info).unwrap();
+
+        release_reserved_margin(&mut participant, &order).unwrap();
+        fill_position(
+            &mut participant,
+            &mut position,
+            &market,
+            order.direction.clone(),
+            order.size,
+            order.leverage,
+            order.margin_mode.clone(),
+            order.limit_price,
+            &FEES,
+        )
+        .unwrap();
+
+        // Filled at the limit price without the spread, paying the 5 bps taker fee
+        assert_eq!(position.entry_price, 140 * USD);
+        assert_eq!(position.size, 2_000_000);
+        assert_eq!(participant.reserved_margin, 0);
+        assert_eq!(participant.used_margin, 28 * USD);
+        assert_eq!(participant.virtual_balance, 1_000 * USD - 140_000);
+        assert_eq!(participant.positions, vec![position_info.key()]);
+    }
+
+    #[test]
+    fn cancelling_releases_the_reservation_once() {
+        let (mut participant, mut order) = placed_long();
+
+        release_reserved_margin(&mut participant, &order).unwrap();
+        order.cancelled_at = 1;
+
+        assert!(!is_order_open(&order));
+        assert_eq!(participant.reserved_margin, 0);
+        assert_eq!(participant.used_margin, 0);
+    }
+
+    #[test]
+    fn liquidation_voids_every_open_reservation() {
+        let (mut participant, order) = placed_long();
+        participant.used_margin += 50 * USD;
+
+        release_all_reserved_margin(&mut participant).unwrap();
+
+        // The order can no longer fill, and cancelling it releases nothing
+        assert!(!is_reservation_held(&participant, &order));
+        assert_eq!(participant.reservation_epoch, 1);
+        assert_eq!(participant.reserved_margin, 0);
+        assert_eq!(participant.used_margin, 50 * USD);
+    }
+}
@@ -1,11 +1,23 @@
 mod process_participant;
+mod expand_participant;
+mod keeper;
 mod open_position;
 mod close_position;
+mod increase_position;
+mod position_triggers;
+mod limit_order;
 mod commit_position;
+mod close_position_account;
 mod reveal_position;
 
 pub use process_participant::*;
+pub use expand_participant::*;
+pub use keeper::*;
 pub use open_position::*;
 pub use close_position::*;
+pub use increase_position::*;
+pub use position_triggers::*;
+pub use limit_order::*;
 pub use commit_position::*;
+pub use close_position_account::*;
 pub use reveal_position::*;
@@ -1,34 +1,54 @@
 /// CAUTION: This instruction is applied only to ER. 
 /// Base Layer's price feed is not updated in realtime.
 use anchor_lang::prelude::*;
-use ephemeral_rollups_sdk::anchor::{delegate};
+use ephemeral_rollups_sdk::anchor::{commit, delegate};
 use ephemeral_rollups_sdk::cpi::DelegateConfig;
+use ephemeral_rollups_sdk::ephem::commit_accounts;
 
 use magicblock_permission_client::instructions::{
     CreateGroupCpiBuilder, CreatePermissionCpiBuilder
 };
 
 use crate::state::{
-    Direction, League, LeagueStatus, Market, Participant, Position, PARTICIPANT_SEED, POSITION_SEED, POSITION_SPACE
+    Direction, League, LeagueStatus, MarginMode, Market, Participant, Position, PositionMode, MARKET_SEED, PARTICIPANT_SEED, POSITION_SEED, POSITION_SPACE
+};
+use crate::events::PositionOpened;
+use crate::instructions::{add_to_position, reduce_position, validate_trigger_prices};
+use crate::utils::{
+    get_price_and_exponent_from_pyth, apply_half_spread, calculate_notional, calculate_required_margin,
+    calculate_liquidation_price, calculate_taker_fee, charge_fee, is_buy, load_participant,
+    price_rounding, resolve_trading_fees, scale_price_to_quote_decimals, TradingFees,
 };
-use crate::utils::{get_price_and_exponent_from_pyth, calculate_notional};
-use crate::constants::QUOTE_DECIMALS;
 
 /// Initialize Position just for delegation
 pub fn init_unopened_position(
     ctx: Context<InitUnopenedPosition>, 
     league: Pubkey,
     current_position_seq: u64,
-    market: Pubkey,
-    market_decimals: u8,
 ) -> Result<()> {
-    // TODO: Check if participant is in the league, user is the participant, market decimal etc...
+    let user = ctx.accounts.user.key();
+    let market = &ctx.accounts.market;
+
+    // The user must have joined the league, and the position must take the next sequence.
+    // open_position and place_order commit the participant whenever they advance it.
+    let participant = load_participant(&ctx.accounts.participant, &league)?;
+    require_keys_eq!(participant.user, user, crate::errors::ErrorCode::InvalidParticipant);
+    require!(
+        current_position_seq == participant.current_position_seq,
+        crate::errors::ErrorCode::InvalidPositionSequence
+    );
+    require!(
+        ctx.accounts.league_account.markets.contains(&market.key()),
+        crate::errors::ErrorCode::MarketNotInLeague
+    );
+
+    // Market params come from the Market account
     let position = &mut ctx.accounts.position;
     position.league = league;
-    position.user = ctx.accounts.user.key();
-    position.market = market;
-    position.market_decimals = market_decimals;
-    position.price_feed = ctx.accounts.price_feed.key();
+    position.user = user;
+    position.market = market.key();
+    position.market_decimals = market.decimals;
+    position.price_feed = market.price_feed;
     position.seq_num = current_position_seq;
 
     position.bump = ctx.bumps.position;
@@ -77,13 +97,29 @@ pub fn create_position_permission(
 }
 
 pub fn delegate_unopened_position(ctx: Context<DelegateUnopenedPosition>, participant: Pubkey, position_seq: u64) -> Result<()> {
-  // TODO: Check if participant is in the league, user is the participant etc...
-  ctx.accounts.delegate_position(
+    let league = ctx.accounts.league.key();
+    let user = ctx.accounts.user.key();
+
+    require_keys_eq!(ctx.accounts.participant.key(), participant, crate::errors::ErrorCode::InvalidParticipant);
+    let participant_account = load_participant(&ctx.accounts.participant, &league)?;
+    require_keys_eq!(participant_account.user, user, crate::errors::ErrorCode::InvalidParticipant);
+
+    // Only positions of this program that are not opened yet can be delegated
+    require_keys_eq!(*ctx.accounts.position.owner, crate::ID, crate::errors::ErrorCode::PositionMismatch);
+    {
+        let position = Position::try_deserialize(&mut &ctx.accounts.position.data.borrow()[..])?;
+        require_keys_eq!(position.league, league, crate::errors::ErrorCode::PositionMismatch);
+        require_keys_eq!(position.user, user, crate::errors::ErrorCode::PositionMismatch);
+        require!(position.seq_num == position_seq, crate::errors::ErrorCode::InvalidPositionSequence);
+        require!(position.opened_at == 0, crate::errors::ErrorCode::PositionAlreadyOpened);
+    }
+
+    ctx.accounts.delegate_position(
         &ctx.accounts.user,
         &[
             POSITION_SEED, 
-            ctx.accounts.league.key().as_ref(),
-            ctx.accounts.user.key().as_ref(),
+            league.as_ref(),
+            user.as_ref(),
             position_seq.to_le_bytes().as_ref()
         ],
         DelegateConfig {
@@ -95,12 +131,15 @@ pub fn delegate_unopened_position(ctx: Context<DelegateUnopenedPosition>, partic
 }
 
 #[allow(unused_variables)]
-pub fn open_position(
-    ctx: Context<OpenPosition>, 
+pub fn open_position<'info>(
+    ctx: Context<'_, '_, 'info, 'info, OpenPosition<'info>>, 
     position_seq: u64,
     direction: Direction,
     size: i64,
     leverage: u8,
+    margin_mode: MarginMode,
+    stop_loss_price: i64,
+    take_profit_price: i64,
 ) -> Result<()> {
     let league = &ctx.accounts.league;
     let market = &ctx.accounts.market;
@@ -108,28 +147,226 @@ pub fn open_position(
     let position = &mut ctx.accounts.position;
 
     require!(league.status == LeagueStatus::Active, crate::errors::ErrorCode::InvalidLeagueStatus);
+    require!(league.markets.contains(&market.key()), crate::errors::ErrorCode::MarketNotInLeague);
+    require!(market.is_active, crate::errors::ErrorCode::MarketNotActive);
+    require!(size > 0, crate::errors::ErrorCode::InvalidPositionSize);
     require!(leverage > 0, crate::errors::ErrorCode::InvalidLeverage);
     require!(leverage <= league.max_leverage, crate::errors::ErrorCode::InvalidLeverage);    
     require!(leverage <= market.max_leverage, crate::errors::ErrorCode::InvalidLeverage);
     require!(market.price_feed == ctx.accounts.price_feed.key(), crate::errors::ErrorCode::OracleMismatch);
-    require!(participant.positions.len() < 10, crate::errors::ErrorCode::MaxOpenPositionExceeded);
     require!(position.opened_at == 0, crate::errors::ErrorCode::PositionAlreadyOpened);
+    require_keys_eq!(position.market, market.key(), crate::errors::ErrorCode::PositionMismatch);
 
-    let (current_price, current_exponent) = get_price_and_exponent_from_pyth(&ctx.accounts.price_feed)?;
+    let (current_price, current_exponent) = get_price_and_exponent_from_pyth(
+        &ctx.accounts.price_feed,
+        &market.feed_id,
+        market.max_price_age_secs,
+        market.max_conf_bps,
+    )?;
     // For equivalent price in decimal, we need to add the quote decimals to the exponent
-    let current_price_in_decimal = (current_price as f64 * 10_f64.powi(-current_exponent + QUOTE_DECIMALS as i32)).floor() as i64;
-    let notional = calculate_notional(current_price_in_decimal, size, market.decimals);
-    // ceil(notional / leverage) option for required margin
-    let required_margin = (notional as f64 / leverage as f64).ceil() as i64;
-    require!(participant.available_balance() >= required_margin, crate::errors::ErrorCode::InsufficientBalance);
+    let current_price_in_decimal = scale_price_to_quote_decimals(
+        current_price,
+        current_exponent,
+        price_rounding(is_buy(&direction, true)),
+    )?;
+    let fees = resolve_trading_fees(league, market);
+
+    // In one-way mode an open position on the same market is netted instead of opening a new one
+    if league.position_mode == PositionMode::OneWay {
+        if let Some(mut existing) = find_market_position(participant, ctx.remaining_accounts, &market.key())? {
+            net_into_position(
+                participant,
+                &mut existing,
+                market,
+                direction,
+                size,
+                leverage,
+                margin_mode,
+                current_price_in_decimal,
+                &fees,
+            )?;
+
+            if existing.closed_at == 0 && (stop_loss_price != 0 || take_profit_price != 0) {
+                validate_trigger_prices(&existing.direction, current_price_in_decimal, stop_loss_price, take_profit_price)?;
+                existing.stop_loss_price = stop_loss_price;
+                existing.take_profit_price = take_profit_price;
+            }
+            existing.exit(&crate::ID)?;
+
+            // The unopened position stays unopened and keeps the current sequence
+            msg!(
+                "Order netted into position {} (size: {}, direction: {})",
+                existing.key(),
+                existing.size,
+                if existing.direction == Direction::Long { "long" } else { "short" }
+            );
+
+            return Ok(());
+        }
+    }
+
+    require!(
+        participant.positions.len() < participant.max_positions as usize,
+        crate::errors::ErrorCode::MaxOpenPositionExceeded
+    );
+    validate_trigger_prices(&direction, current_price_in_decimal, stop_loss_price, take_profit_price)?;
+
+    // Market fills cross the synthetic spread
+    let entry_price = apply_half_spread(current_price_in_decimal, is_buy(&direction, true), fees.half_spread_bps)?;
+    fill_position(
+        participant,
+        position,
+        market,
+        direction,
+        size,
+        leverage,
+        margin_mode,
+        entry_price,
+        &fees,
+    )?;
+    position.stop_loss_price = stop_loss_price;
+    position.take_profit_price = take_profit_price;
+
+    participant.current_position_seq = participant
+        .current_position_seq
+        .checked_add(1)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    // The next init_unopened_position checks the sequence on the base layer copy of the participant
+    participant.exit(&crate::ID)?;
+    position.exit(&crate::ID)?;
+    commit_accounts(
+        &ctx.accounts.user,
+        vec![&participant.to_account_info(), &position.to_account_info()],
+        &ctx.accounts.magic_context,
+        &ctx.accounts.magic_program,
+    )?;
+
+    msg!(
+        "Position opened successfully at price {} (liquidation price: {}), commit requested",
+        entry_price,
+        position.liquidation_price
+    );
+
+    Ok(())
+}
+
+/// Finds the participant's open position on the given market (one-way mode).
+/// Every position of the participant must be passed in the remaining accounts, in order,
+/// so a same-market position can't be left out.
+fn find_market_position<'info>(
+    participant: &Participant,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    market: &Pubkey,
+) -> Result<Option<Account<'info, Position>>> {
+    require!(
+        remaining_accounts.len() == participant.positions.len(),
+        crate::errors::ErrorCode::PositionMismatch
+    );
+
+    for (position_key, position_ai) in participant.positions.iter().zip(remaining_accounts.iter()) {
+        require_keys_eq!(*position_key, position_ai.key(), crate::errors::ErrorCode::PositionMismatch);
+
+        let position = Account::<Position>::try_from(position_ai)?;
+        if position.market == *market && position.size > 0 {
+            return Ok(Some(position));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Nets an order into an open position on the same market (one-way mode).
+/// Same direction adds to the position, which must have the order's leverage and margin mode;
+/// opposite direction reduces it, realizing PnL on the reduced part, and flips it in place
+/// when the order is larger.
+fn net_into_position(
+    participant: &mut Participant,
+    position: &mut Account<Position>,
+    market: &Market,
+    direction: Direction,
+    size: i64,
+    leverage: u8,
+    margin_mode: MarginMode,
+    price_in_decimal: i64,
+    fees: &TradingFees,
+) -> Result<()> {
+    let position_key = position.key();
+    if position.direction == direction {
+        require!(position.leverage == leverage, crate::errors::ErrorCode::InvalidLeverage);
+        require!(position.margin_mode == margin_mode, crate::errors::ErrorCode::MarginModeMismatch);
+        return add_to_position(participant, position, position_key, market, price_in_decimal, size, fees);
+    }
+
+    let close_size = size.min(position.size);
+    reduce_position(participant, position, position_key, market, price_in_decimal, close_size, fees)?;
+
+    let flip_size = size
+        .checked_sub(close_size)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    if flip_size > 0 {
+        // Reopen the same account on the other side as a fresh leg,
+        // the closed leg was reported by PositionClosed and its stats and triggers no longer apply
+        position.closed_at = 0;
+        position.closed_size = 0;
+        position.closed_price = 0;
+        position.closed_equity = 0;
+        position.closed_pnl = 0;
+        position.liquidation_penalty = 0;
+        position.stop_loss_price = 0;
+        position.take_profit_price = 0;
+        let entry_price = apply_half_spread(price_in_decimal, is_buy(&direction, true), fees.half_spread_bps)?;
+        fill_position(
+            participant,
+            position,
+            market,
+            direction,
+            flip_size,
+            leverage,
+            margin_mode,
+            entry_price,
+            fees,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Opens an unopened position at the given entry price, locks its margin and charges the taker fee.
+/// Shared by `open_position` and `fill_order`; the caller owns the position sequence
+/// and applies the spread to the entry price.
+pub(crate) fn fill_position(
+    participant: &mut Participant,
+    position: &mut Account<Position>,
+    market: &Market,
+    direction: Direction,
+    size: i64,
+    leverage: u8,
+    margin_mode: MarginMode,
+    entry_price: i64,
+    fees: &TradingFees,
+) -> Result<()> {
+    let notional = calculate_notional(entry_price, size, position.market_decimals)?;
+    // ceil(notional / leverage) for required margin
+    let required_margin = calculate_required_margin(notional, leverage)?;
+    let fee = calculate_taker_fee(notional, fees.taker_fee_bps)?;
+    require!(
+        participant.available_balance()
+            >= required_margin
+                .checked_add(fee)
+                .ok_or(crate::errors::ErrorCode::MathOverflow)?,
+        crate::errors::ErrorCode::InsufficientBalance
+    );
 
     // Fill out position account
     position.direction = direction;
     position.entry_size = size;
     position.size = size;
-    position.entry_price = current_price_in_decimal;
+    position.entry_price = entry_price;
     position.notional = notional;
     position.leverage = leverage;
+    position.margin_mode = margin_mode;
+    position.liquidation_price = calculate_liquidation_price(position, market.maintenance_margin_bps)?;
     position.opened_at = Clock::get()?.unix_timestamp;
 
     // Update participant with overflow protection
@@ -141,13 +378,24 @@ pub fn open_position(
         .used_margin
         .checked_add(required_margin)
         .ok_or(crate::errors::ErrorCode::MathOverflow)?;
-    participant.current_position_seq = participant
-        .current_position_seq
-        .checked_add(1)
-        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
     participant.positions.push(position.key());
-
-    msg!("Position opened successfully at price {}", current_price_in_decimal);
+    participant.reset_page();
+    charge_fee(participant, fee)?;
+
+    emit!(PositionOpened {
+        league: position.league,
+        user: position.user,
+        position: position.key(),
+        market: position.market,
+        direction: position.direction.clone(),
+        margin_mode: position.margin_mode.clone(),
+        size,
+        entry_price,
+        notional,
+        leverage,
+        fee,
+        timestamp: position.opened_at,
+    });
 
     Ok(())
 }
@@ -169,8 +417,17 @@ pub struct InitUnopenedPosition<'info> {
     )]
     pub position: Account<'info, Position>,
 
-    /// CHECK: Price feed account (Pyth PriceUpdateV2)
-    pub price_feed: AccountInfo<'info>,
+    /// CHECK: Participant account, may be delegated (validated by load_participant)
+    pub participant: AccountInfo<'info>,
+
+    #[account(address = league)]
+    pub league_account: Account<'info, League>,
+
+    #[account(
+        seeds = [MARKET_SEED, market.price_feed.as_ref()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
 
     #[account(mut)]
     pub user: Signer<'info>,
@@ -185,6 +442,9 @@ pub struct DelegateUnopenedPosition<'info> {
     pub user: Signer<'info>,
     pub league: Account<'info, League>,
 
+    /// CHECK: Participant account, may be delegated (validated by load_participant)
+    pub participant: AccountInfo<'info>,
+
     /// CHECK: Position account
     #[account(mut, del)]
     pub position: AccountInfo<'info>,
@@ -192,6 +452,7 @@ pub struct DelegateUnopenedPosition<'info> {
 
 /// Open Position (on ER)
 /// - user open position on ER w/ price feed on ER (price feed is updated in realtime)
+#[commit]
 #[derive(Accounts)]
 #[instruction(position_seq: u64)]
 pub struct OpenPosition<'info> {
@@ -225,6 +486,9 @@ pub struct OpenPosition<'info> {
     pub market: Account<'info, Market>,
     /// CHECK: Price feed account (Pyth PriceUpdateV2)
     pub price_feed: AccountInfo<'info>,
+
+    // Remaining accounts (one-way leagues only):
+    // [position_0, position_1, ...] in the order of participant.positions
 }
 
 /// This is not used because private ER doesn't support realtime price feed
@@ -253,3 +517,129 @@ pub struct CreatePositionPermission<'info> {
 
     // Remaining accounts include the members of the group
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorCode;
+    use crate::state::Order;
+    use crate::test_fixtures::{self, TestAccount, NOW, USD};
+
+    const NO_FEES: TradingFees = TradingFees { taker_fee_bps: 0, half_spread_bps: 0 };
+
+    /// $1,000 balance with 2 SOL long at $150, 10x cross: $30 margin locked.
+    fn long_position() -> (Participant, Position, Pubkey) {
+        let position = test_fixtures::position(Direction::Long, MarginMode::Cross, 150 * USD, 2_000_000);
+        let position_key = Pubkey::new_unique();
+        let mut participant = test_fixtures::participant(1_000 * USD);
+        participant.used_margin = 30 * USD;
+        participant.positions = vec![position_key];
+        (participant, position, position_key)
+    }
+
+    /// Order of `size` at `price` dollars, on the SOL/USD market.
+    fn order(direction: Direction, size: i64, leverage: u8, margin_mode: MarginMode, price: i64) -> Order {
+        let mut order = test_fixtures::order(direction, price * USD, size);
+        order.leverage = leverage;
+        order.margin_mode = margin_mode;
+        order
+    }
+
+    /// Nets a market order, filled at its limit price, into the position, returning the updated position.
+    fn net(
+        participant: &mut Participant,
+        position: &Position,
+        position_key: Pubkey,
+        order: Order,
+    ) -> (Result<()>, Position) {
+        test_fixtures::use_clock();
+        let mut position_account = TestAccount::new(position_key, crate::ID, position);
+        let position_info = position_account.info();
+        let mut position = Account::<Position>::try_from(&position_info).unwrap();
+
+        let result = net_into_position(
+            participant,
+            &mut position,
+            &test_fixtures::market(),
+            order.direction,
+            order.size,
+            order.leverage,
+            order.margin_mode,
+            order.limit_price,
+            &NO_FEES,
+        );
+        (result, (*position).clone())
+    }
+
+    #[test]
+    fn same_direction_adds_to_the_position() {
+        let (mut participant, position, key) = long_position();
+
+        let add = order(Direction::Long, 1_000_000, 10, MarginMode::Cross, 180);
+        let (result, position) = net(&mut participant, &position, key, add);
+        result.unwrap();
+
+        // ($300 + $180) / 3 SOL
+        assert_eq!(position.size, 3_000_000);
+        assert_eq!(position.entry_price, 160 * USD);
+        assert_eq!(participant.used_margin, 48 * USD);
+        assert_eq!(participant.positions, vec![key]);
+    }
+
+    #[test]
+    fn same_direction_requires_the_position_leverage_and_margin_mode() {
+        let (mut participant, position, key) = long_position();
+
+        let other_leverage = order(Direction::Long, 1_000_000, 5, MarginMode::Cross, 150);
+        let (result, _) = net(&mut participant, &position, key, other_leverage);
+        assert_eq!(result.unwrap_err(), ErrorCode::InvalidLeverage.into());
+        let other_margin_mode = order(Direction::Long, 1_000_000, 10, MarginMode::Isolated, 150);
+        let (result, _) = net(&mut participant, &position, key, other_margin_mode);
+        assert_eq!(result.unwrap_err(), ErrorCode::MarginModeMismatch.into());
+    }
+
+    #[test]
+    fn opposite_direction_reduces_the_position() {
+        let (mut participant, position, key) = long_position();
+
+        let reduce = order(Direction::Short, 1_000_000, 10, MarginMode::Cross, 160);
+        let (result, position) = net(&mut participant, &position, key, reduce);
+        result.unwrap();
+
+        assert!(position.direction == Direction::Long);
+        assert_eq!(position.size, 1_000_000);
+        assert_eq!(position.closed_pnl, 10 * USD);
+        assert_eq!(position.closed_at, 0);
+        assert_eq!(participant.virtual_balance, 1_010 * USD);
+        assert_eq!(participant.used_margin, 15 * USD);
+        assert_eq!(participant.positions, vec![key]);
+    }
+
+    #[test]
+    fn larger_opposite_order_flips_the_position() {
+        let (mut participant, mut position, key) = long_position();
+        position.stop_loss_price = 140 * USD;
+
+        let flip = order(Direction::Short, 3_000_000, 5, MarginMode::Isolated, 160);
+        let (result, position) = net(&mut participant, &position, key, flip);
+        result.unwrap();
+
+        // The 2 SOL long closes for +$20, the remaining 1 SOL opens a fresh 5x isolated short
+        assert_eq!(participant.virtual_balance, 1_020 * USD);
+        assert!(position.direction == Direction::Short);
+        assert!(position.margin_mode == MarginMode::Isolated);
+        assert_eq!(position.leverage, 5);
+        assert_eq!(position.size, 1_000_000);
+        assert_eq!(position.entry_size, 1_000_000);
+        assert_eq!(position.entry_price, 160 * USD);
+        assert_eq!(position.opened_at, NOW);
+        assert_eq!(participant.used_margin, 32 * USD);
+        assert_eq!(participant.positions, vec![key]);
+
+        // The closed leg's stats and triggers don't carry over
+        assert_eq!(position.closed_at, 0);
+        assert_eq!(position.closed_size, 0);
+        assert_eq!(position.closed_pnl, 0);
+        assert_eq!(position.stop_loss_price, 0);
+    }
+}
@@ -0,0 +1,325 @@
+/// CAUTION: This instruction is applied only to ER.
+/// Base Layer's price feed is not updated in realtime.
+use anchor_lang::prelude::*;
+use ephemeral_rollups_sdk::anchor::commit;
+use ephemeral_rollups_sdk::ephem::commit_accounts;
+
+use crate::events::PositionTriggersUpdated;
+use crate::instructions::reduce_position;
+use crate::state::{Direction, League, LeagueStatus, Market, Participant, Position, LEAGUE_SEED, PARTICIPANT_SEED, POSITION_SEED};
+use crate::utils::{get_price_and_exponent_from_pyth, is_buy, price_rounding, resolve_trading_fees, scale_price_to_quote_decimals, TradingFees};
+
+#[allow(unused_variables)]
+pub fn update_position_triggers(
+    ctx: Context<UpdatePositionTriggers>,
+    position_seq: u64,
+    stop_loss_price: i64,
+    take_profit_price: i64,
+) -> Result<()> {
+    let league = &ctx.accounts.league;
+    let market = &ctx.accounts.market;
+    let position = &mut ctx.accounts.position;
+
+    require!(league.status == LeagueStatus::Active, crate::errors::ErrorCode::InvalidLeagueStatus);
+    require!(position.opened_at != 0, crate::errors::ErrorCode::PositionNotOpened);
+    require!(position.closed_at == 0, crate::errors::ErrorCode::PositionAlreadyClosed);
+    require_keys_eq!(position.market, market.key(), crate::errors::ErrorCode::PositionMismatch);
+    require!(market.price_feed == ctx.accounts.price_feed.key(), crate::errors::ErrorCode::OracleMismatch);
+
+    let current_price_in_decimal = current_price(&ctx.accounts.price_feed, market, position)?;
+    validate_trigger_prices(&position.direction, current_price_in_decimal, stop_loss_price, take_profit_price)?;
+
+    position.stop_loss_price = stop_loss_price;
+    position.take_profit_price = take_profit_price;
+
+    msg!(
+        "Position triggers updated: stop_loss {}, take_profit {}",
+        stop_loss_price,
+        take_profit_price
+    );
+
+    emit!(PositionTriggersUpdated {
+        league: position.league,
+        user: position.user,
+        position: position.key(),
+        stop_loss_price,
+        take_profit_price,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Closes the whole position once the oracle price crosses its stop-loss or take-profit.
+/// The trigger prices were set by the user, so any keeper may execute them on the user's behalf.
+#[allow(unused_variables)]
+pub fn execute_trigger(ctx: Context<ExecuteTrigger>, user: Pubkey, position_seq: u64) -> Result<()> {
+    let league = &ctx.accounts.league;
+    let market = &ctx.accounts.market;
+    let participant = &mut ctx.accounts.participant;
+    let position = &mut ctx.accounts.position;
+
+    require!(league.status == LeagueStatus::Active, crate::errors::ErrorCode::InvalidLeagueStatus);
+    require!(position.opened_at != 0, crate::errors::ErrorCode::PositionNotOpened);
+    require!(position.closed_at == 0, crate::errors::ErrorCode::PositionAlreadyClosed);
+    require_keys_eq!(position.market, market.key(), crate::errors::ErrorCode::PositionMismatch);
+    require!(market.price_feed == ctx.accounts.price_feed.key(), crate::errors::ErrorCode::OracleMismatch);
+
+    let current_price_in_decimal = current_price(&ctx.accounts.price_feed, market, position)?;
+    let position_key = position.key();
+    let fees = resolve_trading_fees(league, market);
+    let (stop_loss_hit, realized_pnl) = close_on_trigger(
+        participant,
+        position,
+        position_key,
+        market,
+        current_price_in_decimal,
+        &fees,
+    )?;
+
+    commit_accounts(
+        &ctx.accounts.keeper,
+        vec![&participant.to_account_info(), &position.to_account_info()],
+        &ctx.accounts.magic_context,
+        &ctx.accounts.magic_program,
+    )?;
+
+    msg!(
+        "{} triggered at price {} (realized_pnl: {}), commit requested",
+        if stop_loss_hit { "Stop loss" } else { "Take profit" },
+        current_price_in_decimal,
+        realized_pnl
+    );
+
+    Ok(())
+}
+
+/// Stop-loss must sit on the losing side and take-profit on the winning side of the reference price.
+/// A trigger price of 0 means not set.
+pub(crate) fn validate_trigger_prices(
+    direction: &Direction,
+    reference_price: i64,
+    stop_loss_price: i64,
+    take_profit_price: i64,
+) -> Result<()> {
+    require!(
+        stop_loss_price >= 0 && take_profit_price >= 0,
+        crate::errors::ErrorCode::InvalidTriggerPrice
+    );
+
+    let (stop_loss_ok, take_profit_ok) = match direction {
+        Direction::Long => (
+            stop_loss_price < reference_price,
+            take_profit_price > reference_price,
+        ),
+        Direction::Short => (
+            stop_loss_price > reference_price,
+            take_profit_price < reference_price,
+        ),
+    };
+    require!(
+        (stop_loss_price == 0 || stop_loss_ok) && (take_profit_price == 0 || take_profit_ok),
+        crate::errors::ErrorCode::InvalidTriggerPrice
+    );
+
+    Ok(())
+}
+
+/// Closes the whole position at `price` once it crosses the stop-loss or take-profit.
+/// Returns whether the stop-loss fired, and the realized PnL.
+fn close_on_trigger(
+    participant: &mut Participant,
+    position: &mut Position,
+    position_key: Pubkey,
+    market: &Market,
+    price: i64,
+    fees: &TradingFees,
+) -> Result<(bool, i64)> {
+    let stop_loss_hit = is_stop_loss_hit(position, price);
+    let take_profit_hit = is_take_profit_hit(position, price);
+    require!(stop_loss_hit || take_profit_hit, crate::errors::ErrorCode::TriggerNotReached);
+
+    let close_size = position.size;
+    let realized_pnl = reduce_position(participant, position, position_key, market, price, close_size, fees)?;
+
+    Ok((stop_loss_hit, realized_pnl))
+}
+
+fn is_stop_loss_hit(position: &Position, price: i64) -> bool {
+    if position.stop_loss_price == 0 {
+        return false;
+    }
+
+    match position.direction {
+        Direction::Long => price <= position.stop_loss_price,
+        Direction::Short => price >= position.stop_loss_price,
+    }
+}
+
+fn is_take_profit_hit(position: &Position, price: i64) -> bool {
+    if position.take_profit_price == 0 {
+        return false;
+    }
+
+    match position.direction {
+        Direction::Long => price >= position.take_profit_price,
+        Direction::Short => price <= position.take_profit_price,
+    }
+}
+
+/// Oracle price at the side that would close the position.
+fn current_price(price_feed: &AccountInfo, market: &Market, position: &Position) -> Result<i64> {
+    let (price, exponent) = get_price_and_exponent_from_pyth(
+        price_feed,
+        &market.feed_id,
+        market.max_price_age_secs,
+        market.max_conf_bps,
+    )?;
+    scale_price_to_quote_decimals(price, exponent, price_rounding(is_buy(&position.direction, false)))
+}
+
+/// Update Position Triggers (on ER)
+/// - user sets or clears the stop-loss / take-profit of an open position
+#[derive(Accounts)]
+#[instruction(position_seq: u64)]
+pub struct UpdatePositionTriggers<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            POSITION_SEED,
+            league.key().as_ref(),
+            user.key().as_ref(),
+            position_seq.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        seeds = [LEAGUE_SEED, league.creator.as_ref(), league.id.as_bytes()],
+        bump = league.bump
+    )]
+    pub league: Account<'info, League>,
+    pub market: Account<'info, Market>,
+    /// CHECK: Price feed account (Pyth PriceUpdateV2)
+    pub price_feed: AccountInfo<'info>,
+}
+
+/// Execute Trigger (on ER)
+/// - keeper closes a position whose stop-loss or take-profit has been crossed
+#[commit]
+#[derive(Accounts)]
+#[instruction(user: Pubkey, position_seq: u64)]
+pub struct ExecuteTrigger<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            POSITION_SEED,
+            league.key().as_ref(),
+            user.as_ref(),
+            position_seq.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        mut,
+        seeds = [
+            PARTICIPANT_SEED,
+            league.key().as_ref(),
+            user.as_ref()
+        ],
+        bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        seeds = [LEAGUE_SEED, league.creator.as_ref(), league.id.as_bytes()],
+        bump = league.bump
+    )]
+    pub league: Account<'info, League>,
+    pub market: Account<'info, Market>,
+    /// CHECK: Price feed account (Pyth PriceUpdateV2)
+    pub price_feed: AccountInfo<'info>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorCode;
+    use crate::state::MarginMode;
+    use crate::test_fixtures::{self, NOW, USD};
+
+    const NO_FEES: TradingFees = TradingFees { taker_fee_bps: 0, half_spread_bps: 0 };
+
+    #[test]
+    fn triggers_must_sit_on_their_side_of_the_price() {
+        validate_trigger_prices(&Direction::Long, 150 * USD, 140 * USD, 160 * USD).unwrap();
+        validate_trigger_prices(&Direction::Short, 150 * USD, 160 * USD, 140 * USD).unwrap();
+        // 0 leaves a trigger unset
+        validate_trigger_prices(&Direction::Long, 150 * USD, 0, 0).unwrap();
+        validate_trigger_prices(&Direction::Short, 150 * USD, 160 * USD, 0).unwrap();
+
+        for (direction, stop_loss, take_profit) in [
+            (Direction::Long, 150 * USD, 0),
+            (Direction::Long, 0, 140 * USD),
+            (Direction::Short, 140 * USD, 0),
+            (Direction::Short, 0, 160 * USD),
+            (Direction::Long, -1, 0),
+        ] {
+            assert_eq!(
+                validate_trigger_prices(&direction, 150 * USD, stop_loss, take_profit).unwrap_err(),
+                ErrorCode::InvalidTriggerPrice.into()
+            );
+        }
+    }
+
+    #[test]
+    fn triggers_fire_once_the_price_crosses_them() {
+        let mut long = test_fixtures::position(Direction::Long, MarginMode::Cross, 150 * USD, 2_000_000);
+        long.stop_loss_price = 140 * USD;
+        long.take_profit_price = 160 * USD;
+        assert!(!is_stop_loss_hit(&long, 141 * USD) && !is_take_profit_hit(&long, 159 * USD));
+        assert!(is_stop_loss_hit(&long, 140 * USD) && is_take_profit_hit(&long, 160 * USD));
+
+        let mut short = test_fixtures::position(Direction::Short, MarginMode::Cross, 150 * USD, 2_000_000);
+        short.stop_loss_price = 160 * USD;
+        assert!(!is_stop_loss_hit(&short, 159 * USD) && is_stop_loss_hit(&short, 161 * USD));
+        // An unset take-profit never fires
+        assert!(!is_take_profit_hit(&short, 1));
+    }
+
+    #[test]
+    fn trigger_closes_the_whole_position() {
+        test_fixtures::use_clock();
+        let market = test_fixtures::market();
+        let mut position = test_fixtures::position(Direction::Long, MarginMode::Cross, 150 * USD, 2_000_000);
+        position.stop_loss_price = 140 * USD;
+        let position_key = Pubkey::new_unique();
+        let mut participant = test_fixtures::participant(10_000 * USD);
+        participant.used_margin = 30 * USD;
+        participant.positions.push(position_key);
+
+        assert_eq!(
+            close_on_trigger(&mut participant, &mut position, position_key, &market, 141 * USD, &NO_FEES)
+                .unwrap_err(),
+            ErrorCode::TriggerNotReached.into()
+        );
+
+        let (stop_loss_hit, realized_pnl) =
+            close_on_trigger(&mut participant, &mut position, position_key, &market, 139 * USD, &NO_FEES).unwrap();
+
+        assert!(stop_loss_hit);
+        assert_eq!(realized_pnl, -22 * USD);
+        assert_eq!(position.size, 0);
+        assert_eq!(position.closed_at, NOW);
+        assert_eq!(participant.virtual_balance, 9_978 * USD);
+        assert!(participant.positions.is_empty());
+    }
+}
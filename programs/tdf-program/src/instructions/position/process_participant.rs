@@ -5,9 +5,11 @@ use ephemeral_rollups_sdk::ephem::{commit_accounts, commit_and_undelegate_accoun
 // Note: MagicInstructionBuilder, MagicAction, CallHandler, CommitType, ActionArgs, ShortAccountMeta
 // are commented out but kept for future use in leaderboard updates
 
-use crate::state::{LEADERBOARD_SEED, PARTICIPANT_SEED, Participant, Position};
-use crate::utils::{get_price_and_exponent_from_pyth, calculate_notional, calculate_unrealized_pnl, calculate_price_from_notional_and_size};
-use crate::constants::QUOTE_DECIMALS;
+use crate::state::{Direction, Keeper, MarginMode, Market, KEEPER_SEED, LEADERBOARD_SEED, PARTICIPANT_SEED, Participant, Position};
+use crate::utils::{get_price_and_exponent_from_pyth, calculate_notional, calculate_unrealized_pnl, calculate_price_from_notional_and_size, calculate_required_margin, calculate_bps_share, calculate_liquidation_price, is_buy, price_rounding, scale_price_to_quote_decimals, Rounding};
+use crate::constants::{KEEPER_REWARD_BPS, LIQUIDATION_PENALTY_BPS};
+use crate::events::{ParticipantLiquidated, ParticipantUpdated, PositionLiquidated};
+use crate::instructions::release_all_reserved_margin;
 
 pub fn delegate_participant(ctx: Context<DelegateParticipant>, league: Pubkey) -> Result<()> {
     let user = &ctx.accounts.user;
@@ -39,27 +41,58 @@ pub fn undelegate_participant(ctx: Context<UndelegateParticipant>, league: Pubke
     Ok(())
 }
 
+/// Remaining accounts per position: [position, market, price_feed].
+/// The market and feed of positions on the same market may repeat, the transaction dedupes them.
+const ACCOUNTS_PER_POSITION: usize = 3;
+
+/// Positions closed and penalties charged while updating a participant.
+#[derive(Default)]
+struct LiquidationSummary {
+    positions_liquidated: u16,
+    total_penalty: i64,
+}
+
+impl LiquidationSummary {
+    fn record(&mut self, position: &Position) -> Result<()> {
+        self.positions_liquidated = self
+            .positions_liquidated
+            .checked_add(1)
+            .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+        self.total_penalty = self
+            .total_penalty
+            .checked_add(position.liquidation_penalty)
+            .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
 /// Internal function containing the core participant update logic.
-/// This is shared between `update_participant` and `update_and_commit_participant`.
+/// This is shared between `update_participant`, `update_and_commit_participant` and `liquidate_participant`.
 fn update_participant_logic<'info>(
     participant: &mut Account<'info, Participant>,
     remaining_accounts: &[AccountInfo<'info>],
-) -> Result<()> {
+) -> Result<LiquidationSummary> {
     let position_keys = &participant.positions.clone();
 
     require!(
-        remaining_accounts.len() == position_keys.len() * 2,
+        remaining_accounts.len() == position_keys.len() * ACCOUNTS_PER_POSITION,
         crate::errors::ErrorCode::InvalidUpdateParticipantRemainingAccounts
     );
 
     let mut total_upnl: i64 = 0;
     let mut total_used_margin: i64 = 0;
+    let mut cross_upnl: i64 = 0;
+    let mut cross_maintenance_margin: i64 = 0;
+    let mut isolated_margin: i64 = 0;
+    let mut liquidated_isolated: Vec<Pubkey> = Vec::new();
+    let mut summary = LiquidationSummary::default();
     let mut prices: Vec<i64> = Vec::new();
 
     // Update positions and calculate PnL
     for (i, position_key) in position_keys.iter().enumerate() {
-        let position_ai = &remaining_accounts[i * 2];
-        let price_feed_ai = &remaining_accounts[i * 2 + 1];
+        let position_ai = &remaining_accounts[i * ACCOUNTS_PER_POSITION];
+        let market_ai = &remaining_accounts[i * ACCOUNTS_PER_POSITION + 1];
+        let price_feed_ai = &remaining_accounts[i * ACCOUNTS_PER_POSITION + 2];
 
         require_keys_eq!(
             *position_key,
@@ -76,14 +109,19 @@ fn update_participant_logic<'info>(
             continue;
         }
 
-        require_keys_eq!(
-            position.price_feed,
-            price_feed_ai.key(),
-            crate::errors::ErrorCode::OracleMismatch
-        );
-
-        let (price, exponent) = get_price_and_exponent_from_pyth(&price_feed_ai)?;
-        let price_in_decimal = (price as f64 * 10_f64.powi(-exponent + QUOTE_DECIMALS as i32)).floor() as i64;
+        let market = load_position_market(&position, market_ai, price_feed_ai)?;
+        let (price, exponent) = get_price_and_exponent_from_pyth(
+            price_feed_ai,
+            &market.feed_id,
+            market.max_price_age_secs,
+            market.max_conf_bps,
+        )?;
+        // Valued at the closing side, so margin checks and liquidations use the trader-unfavourable price
+        let price_in_decimal = scale_price_to_quote_decimals(
+            price,
+            exponent,
+            price_rounding(is_buy(&position.direction, false)),
+        )?;
         prices.push(price_in_decimal);
 
         let new_upnl = calculate_unrealized_pnl(
@@ -92,9 +130,29 @@ fn update_participant_logic<'info>(
             position.size,
             position.market_decimals,
             position.direction.clone(),
-        );
+        )?;
 
         position.unrealized_pnl = new_upnl;
+        // Follows changes of the market's maintenance margin
+        position.liquidation_price = calculate_liquidation_price(&position, market.maintenance_margin_bps)?;
+
+        // Isolated positions are liquidated on their own once the price crosses the liquidation price
+        if is_isolated_liquidatable(&position, price_in_decimal) {
+            let realized_pnl = liquidate_position(participant, &mut position, *position_key, price_in_decimal)?;
+            liquidated_isolated.push(*position_key);
+            summary.record(&position)?;
+
+            msg!(
+                "💥 Isolated position liquidated: {} (realized_pnl: {}, penalty: {})",
+                position_key,
+                realized_pnl,
+                position.liquidation_penalty
+            );
+
+            let mut dst = &mut data[..];
+            position.try_serialize(&mut dst)?;
+            continue;
+        }
 
         let mut dst = &mut data[..];
         position.try_serialize(&mut dst)?;
@@ -103,126 +161,455 @@ fn update_participant_logic<'info>(
             .checked_add(new_upnl)
             .ok_or(crate::errors::ErrorCode::MathOverflow)?;
 
-        let margin_for_pos = position
-            .notional
-            .checked_div(position.leverage as i64)
-            .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+        let margin_for_pos = calculate_required_margin(position.notional, position.leverage)?;
         total_used_margin = total_used_margin
             .checked_add(margin_for_pos)
             .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+        match position.margin_mode {
+            MarginMode::Isolated => {
+                isolated_margin = isolated_margin
+                    .checked_add(margin_for_pos)
+                    .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+            }
+            MarginMode::Cross => {
+                cross_upnl = cross_upnl
+                    .checked_add(new_upnl)
+                    .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+                // Maintenance margin on the current value of the position
+                let position_value = calculate_notional(price_in_decimal, position.size, position.market_decimals)?;
+                let maintenance_margin = calculate_bps_share(position_value, market.maintenance_margin_bps, Rounding::Up)?;
+                cross_maintenance_margin = cross_maintenance_margin
+                    .checked_add(maintenance_margin)
+                    .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+            }
+        }
     }
 
     participant.unrealized_pnl = total_upnl;
-    participant.used_margin = total_used_margin;
+    // Margin reserved by open limit orders stays locked
+    participant.used_margin = total_used_margin
+        .checked_add(participant.reserved_margin)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    participant.positions.retain(|key| !liquidated_isolated.contains(key));
+    // Every position is marked to market here, which supersedes a paged pass in progress
+    participant.reset_page();
+    participant.page_liquidating = false;
+
+    // Cross equity excludes the margin locked in isolated positions and their PnL
+    let cross_equity = participant
+        .virtual_balance
+        .checked_sub(isolated_margin)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?
+        .checked_add(cross_upnl)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
 
     msg!(
-        "Participant updated: unrealized_pnl: {}, used_margin: {}, equity: {}",
+        "Participant updated: unrealized_pnl: {}, used_margin: {}, cross_equity: {}, cross_maintenance_margin: {}, equity: {}",
         total_upnl,
         total_used_margin,
+        cross_equity,
+        cross_maintenance_margin,
         participant.equity()
     );
 
-    // Handle liquidation if equity is negative
-    if participant.equity() < 0 {
-        liquidate_participant_positions(participant, position_keys, remaining_accounts, &prices)?;
+    // Handle liquidation if cross equity falls below the cross maintenance margin
+    if cross_maintenance_margin > 0 && cross_equity < cross_maintenance_margin {
+        liquidate_participant_positions(participant, position_keys, remaining_accounts, &prices, &mut summary)?;
     }
 
-    Ok(())
+    emit!(ParticipantUpdated {
+        league: participant.league,
+        user: participant.user,
+        unrealized_pnl: participant.unrealized_pnl,
+        used_margin: participant.used_margin,
+        equity: participant.equity(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(summary)
 }
 
-/// Internal function to handle liquidation of all positions when equity is negative.
-fn liquidate_participant_positions<'info>(
+/// Internal function for a paginated participant update.
+/// Marks `positions[offset..offset + n]` to market, where n is the number of (position, market, price feed)
+/// triples in the remaining accounts, and applies each position's PnL change as a delta so equity
+/// stays consistent across pages. Pages of a pass must be sent in order starting at offset 0: the pass
+/// accumulates margin and cross totals on the participant, and the last page runs the same cross
+/// maintenance margin check as `update_participant`. A breached check flags the participant, and the
+/// next pass liquidates every cross position page by page.
+/// Isolated positions are liquidated as usual. Liquidated positions are removed from `positions`,
+/// so the next page starts at `participant.page_offset`.
+fn update_participant_page_logic<'info>(
     participant: &mut Account<'info, Participant>,
-    position_keys: &[Pubkey],
+    offset: u16,
     remaining_accounts: &[AccountInfo<'info>],
-    prices: &[i64],
 ) -> Result<()> {
-    msg!("💥 Auto liquidation triggered");
+    let start = offset as usize;
+    let count = remaining_accounts.len() / ACCOUNTS_PER_POSITION;
+
+    require!(
+        offset == 0 || offset == participant.page_offset,
+        crate::errors::ErrorCode::InvalidPageOffset
+    );
+    require!(
+        remaining_accounts.len() % ACCOUNTS_PER_POSITION == 0 && count > 0 && start + count <= participant.positions.len(),
+        crate::errors::ErrorCode::InvalidUpdateParticipantRemainingAccounts
+    );
+
+    // A new pass starts over its running totals
+    if offset == 0 {
+        participant.page_used_margin = 0;
+        participant.page_cross_pnl = 0;
+        participant.page_maintenance_margin = 0;
+    }
+    let liquidating = participant.page_liquidating;
+
+    let position_keys = participant.positions[start..start + count].to_vec();
+    let mut liquidated: Vec<Pubkey> = Vec::new();
 
     for (i, position_key) in position_keys.iter().enumerate() {
-        let position_ai = &remaining_accounts[i * 2];
+        let position_ai = &remaining_accounts[i * ACCOUNTS_PER_POSITION];
+        let market_ai = &remaining_accounts[i * ACCOUNTS_PER_POSITION + 1];
+        let price_feed_ai = &remaining_accounts[i * ACCOUNTS_PER_POSITION + 2];
+
+        require_keys_eq!(
+            *position_key,
+            position_ai.key(),
+            crate::errors::ErrorCode::PositionMismatch
+        );
 
         let mut data = position_ai.try_borrow_mut_data()?;
         let mut position: Position = Position::try_deserialize(&mut &data[..])?;
 
+        // if position is closed, skip
         if position.size == 0 {
             continue;
         }
 
-        let price = prices[i];
-        let realized_pnl = position.unrealized_pnl;
-        let released_margin = position
-            .notional
-            .checked_div(position.leverage as i64)
-            .ok_or(crate::errors::ErrorCode::MathOverflow)?;
-        let closing_equity = calculate_notional(price, position.size, position.market_decimals);
+        let market = load_position_market(&position, market_ai, price_feed_ai)?;
+        let (price, exponent) = get_price_and_exponent_from_pyth(
+            price_feed_ai,
+            &market.feed_id,
+            market.max_price_age_secs,
+            market.max_conf_bps,
+        )?;
+        let price_in_decimal = scale_price_to_quote_decimals(
+            price,
+            exponent,
+            price_rounding(is_buy(&position.direction, false)),
+        )?;
 
-        // Calculate closed stats with overflow protection
-        position.closed_size = position
-            .closed_size
-            .checked_add(position.size)
-            .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+        let new_upnl = calculate_unrealized_pnl(
+            position.notional,
+            price_in_decimal,
+            position.size,
+            position.market_decimals,
+            position.direction.clone(),
+        )?;
 
-        position.closed_equity = position
-            .closed_equity
-            .checked_add(closing_equity)
+        // Apply the change of this position's PnL to the participant
+        let upnl_delta = new_upnl
+            .checked_sub(position.unrealized_pnl)
             .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+        participant.unrealized_pnl = participant
+            .unrealized_pnl
+            .checked_add(upnl_delta)
+            .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+        position.unrealized_pnl = new_upnl;
+        position.liquidation_price = calculate_liquidation_price(&position, market.maintenance_margin_bps)?;
 
-        // Safe division for closed_price
-        if position.closed_size > 0 {
-            position.closed_price = calculate_price_from_notional_and_size(
-                position.closed_equity,
-                position.closed_size,
-                position.market_decimals,
+        let cross_liquidation = liquidating && position.margin_mode == MarginMode::Cross;
+        if cross_liquidation || is_isolated_liquidatable(&position, price_in_decimal) {
+            let realized_pnl = liquidate_position(participant, &mut position, *position_key, price_in_decimal)?;
+            liquidated.push(*position_key);
+
+            msg!(
+                "💥 Position liquidated: {} (realized_pnl: {}, penalty: {})",
+                position_key,
+                realized_pnl,
+                position.liquidation_penalty
             );
+
+            let mut dst = &mut data[..];
+            position.try_serialize(&mut dst)?;
+            continue;
         }
 
-        position.closed_pnl = position
-            .closed_pnl
-            .checked_add(realized_pnl)
+        let mut dst = &mut data[..];
+        position.try_serialize(&mut dst)?;
+
+        let margin_for_pos = calculate_required_margin(position.notional, position.leverage)?;
+        participant.page_used_margin = participant
+            .page_used_margin
+            .checked_add(margin_for_pos)
             .ok_or(crate::errors::ErrorCode::MathOverflow)?;
 
-        // Update position
-        position.size = 0;
-        position.notional = 0;
-        position.unrealized_pnl = 0;
-        position.closed_at = Clock::get()?.unix_timestamp;
+        match position.margin_mode {
+            MarginMode::Isolated => {
+                participant.page_cross_pnl = participant
+                    .page_cross_pnl
+                    .checked_sub(margin_for_pos)
+                    .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+            }
+            MarginMode::Cross => {
+                participant.page_cross_pnl = participant
+                    .page_cross_pnl
+                    .checked_add(new_upnl)
+                    .ok_or(crate::errors::ErrorCode::MathOverflow)?;
 
-        // Update participant with overflow protection
-        participant.total_volume = participant
-            .total_volume
-            .checked_add(closing_equity)
-            .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+                // Maintenance margin on the current value of the position
+                let position_value = calculate_notional(price_in_decimal, position.size, position.market_decimals)?;
+                let maintenance_margin = calculate_bps_share(position_value, market.maintenance_margin_bps, Rounding::Up)?;
+                participant.page_maintenance_margin = participant
+                    .page_maintenance_margin
+                    .checked_add(maintenance_margin)
+                    .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+            }
+        }
+    }
 
+    participant.positions.retain(|key| !liquidated.contains(key));
+    let next_offset = start + count - liquidated.len();
+
+    if next_offset < participant.positions.len() {
+        participant.page_offset = next_offset as u16;
+    } else {
+        // Last page: every position of the pass is marked to market
+        participant.reset_page();
         participant.used_margin = participant
-            .used_margin
-            .checked_sub(released_margin)
+            .page_used_margin
+            .checked_add(participant.reserved_margin)
             .ok_or(crate::errors::ErrorCode::MathOverflow)?;
 
-        participant.virtual_balance = participant
-            .virtual_balance
-            .checked_add(realized_pnl)
-            .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+        if liquidating {
+            participant.page_liquidating = false;
+            msg!("All cross positions liquidated.");
+        } else {
+            let cross_equity = participant
+                .virtual_balance
+                .checked_add(participant.page_cross_pnl)
+                .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+            msg!(
+                "Participant pass completed: cross_equity: {}, cross_maintenance_margin: {}",
+                cross_equity,
+                participant.page_maintenance_margin
+            );
+
+            // Cross positions are liquidated by the next pass, open orders can't fill meanwhile
+            if participant.page_maintenance_margin > 0 && cross_equity < participant.page_maintenance_margin {
+                msg!("💥 Auto liquidation triggered, next pass liquidates cross positions");
+                participant.page_liquidating = true;
+                release_all_reserved_margin(participant)?;
+            }
+        }
+    }
+
+    emit!(ParticipantUpdated {
+        league: participant.league,
+        user: participant.user,
+        unrealized_pnl: participant.unrealized_pnl,
+        used_margin: participant.used_margin,
+        equity: participant.equity(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!(
+        "Participant page updated: positions {}..{}, unrealized_pnl: {}, equity: {}",
+        start,
+        start + count,
+        participant.unrealized_pnl,
+        participant.equity()
+    );
+
+    Ok(())
+}
+
+/// Loads a position's Market from the remaining accounts, so positions are always priced
+/// with the market's current oracle settings.
+fn load_position_market(
+    position: &Position,
+    market_ai: &AccountInfo,
+    price_feed_ai: &AccountInfo,
+) -> Result<Market> {
+    require_keys_eq!(position.market, market_ai.key(), crate::errors::ErrorCode::InvalidMarket);
+    require_keys_eq!(*market_ai.owner, crate::ID, crate::errors::ErrorCode::InvalidMarket);
+    let market = Market::try_deserialize(&mut &market_ai.data.borrow()[..])?;
+    require_keys_eq!(market.price_feed, price_feed_ai.key(), crate::errors::ErrorCode::OracleMismatch);
+
+    Ok(market)
+}
+
+/// Whether an isolated position's price has crossed its liquidation price.
+fn is_isolated_liquidatable(position: &Position, price: i64) -> bool {
+    if position.margin_mode != MarginMode::Isolated || position.liquidation_price == 0 {
+        return false;
+    }
+
+    match position.direction {
+        Direction::Long => price <= position.liquidation_price,
+        Direction::Short => price >= position.liquidation_price,
+    }
+}
+
+/// Internal function to handle liquidation of all cross positions when cross equity is below maintenance margin.
+/// Isolated positions are left untouched, open-order reservations are released.
+fn liquidate_participant_positions<'info>(
+    participant: &mut Account<'info, Participant>,
+    position_keys: &[Pubkey],
+    remaining_accounts: &[AccountInfo<'info>],
+    prices: &[i64],
+    summary: &mut LiquidationSummary,
+) -> Result<()> {
+    msg!("💥 Auto liquidation triggered");
+
+    // Open orders can't fill on a liquidated account, free their margin
+    release_all_reserved_margin(participant)?;
+
+    let mut liquidated: Vec<Pubkey> = Vec::new();
+
+    for (i, position_key) in position_keys.iter().enumerate() {
+        let position_ai = &remaining_accounts[i * ACCOUNTS_PER_POSITION];
+
+        let mut data = position_ai.try_borrow_mut_data()?;
+        let mut position: Position = Position::try_deserialize(&mut &data[..])?;
+
+        if position.size == 0 || position.margin_mode != MarginMode::Cross {
+            continue;
+        }
+
+        let realized_pnl = liquidate_position(participant, &mut position, *position_key, prices[i])?;
+        liquidated.push(*position_key);
+        summary.record(&position)?;
 
         msg!(
-            "Position liquidated: {} (realized_pnl: {}, released_margin: {})",
+            "Position liquidated: {} (realized_pnl: {}, penalty: {})",
             position_key,
             realized_pnl,
-            released_margin
+            position.liquidation_penalty
         );
 
         let mut dst = &mut data[..];
         position.try_serialize(&mut dst)?;
     }
 
-    // Clear all positions after liquidation
-    participant.positions.clear();
-    participant.unrealized_pnl = 0;
+    // Remove liquidated positions, isolated positions stay open
+    participant.positions.retain(|key| !liquidated.contains(key));
 
-    msg!("All positions liquidated. Participant equity reset.");
+    msg!("All cross positions liquidated.");
     Ok(())
 }
 
+/// Closes a position at the oracle price, realizing its unrealized PnL and
+/// charging the liquidation penalty on the closed value.
+/// An isolated position's loss and penalty are capped at its own margin;
+/// a cross position's penalty is capped at the participant's remaining equity.
+/// Returns the realized PnL.
+fn liquidate_position(
+    participant: &mut Participant,
+    position: &mut Position,
+    position_key: Pubkey,
+    price: i64,
+) -> Result<i64> {
+    let released_margin = calculate_required_margin(position.notional, position.leverage)?;
+    let realized_pnl = match position.margin_mode {
+        MarginMode::Isolated => position.unrealized_pnl.max(-released_margin),
+        MarginMode::Cross => position.unrealized_pnl,
+    };
+    let closing_equity = calculate_notional(price, position.size, position.market_decimals)?;
+    let liquidated_size = position.size;
+
+    // Calculate closed stats with overflow protection
+    position.closed_size = position
+        .closed_size
+        .checked_add(position.size)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    position.closed_equity = position
+        .closed_equity
+        .checked_add(closing_equity)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    // Safe division for closed_price
+    if position.closed_size > 0 {
+        position.closed_price = calculate_price_from_notional_and_size(
+            position.closed_equity,
+            position.closed_size,
+            position.market_decimals,
+        )?;
+    }
+
+    position.closed_pnl = position
+        .closed_pnl
+        .checked_add(realized_pnl)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    // Move the PnL from unrealized to realized
+    participant.unrealized_pnl = participant
+        .unrealized_pnl
+        .checked_sub(position.unrealized_pnl)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    participant.virtual_balance = participant
+        .virtual_balance
+        .checked_add(realized_pnl)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    // Update position
+    position.size = 0;
+    position.notional = 0;
+    position.unrealized_pnl = 0;
+    position.liquidation_price = 0;
+    position.closed_at = Clock::get()?.unix_timestamp;
+
+    // Update participant with overflow protection
+    participant.total_volume = participant
+        .total_volume
+        .checked_add(closing_equity)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    participant.used_margin = participant
+        .used_margin
+        .checked_sub(released_margin)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    // Liquidation penalty, never exceeding what is left to lose
+    let penalty_cap = match position.margin_mode {
+        MarginMode::Isolated => released_margin
+            .checked_add(realized_pnl)
+            .ok_or(crate::errors::ErrorCode::MathOverflow)?,
+        MarginMode::Cross => participant.equity(),
+    };
+    let penalty = calculate_bps_share(closing_equity, LIQUIDATION_PENALTY_BPS, Rounding::Down)?
+        .min(penalty_cap.max(0));
+    position.liquidation_penalty = position
+        .liquidation_penalty
+        .checked_add(penalty)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    participant.virtual_balance = participant
+        .virtual_balance
+        .checked_sub(penalty)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    participant.total_liquidation_penalty = participant
+        .total_liquidation_penalty
+        .checked_add(penalty)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    emit!(PositionLiquidated {
+        league: position.league,
+        user: position.user,
+        position: position_key,
+        market: position.market,
+        margin_mode: position.margin_mode.clone(),
+        size: liquidated_size,
+        price,
+        realized_pnl,
+        penalty,
+        timestamp: position.closed_at,
+    });
+
+    Ok(realized_pnl)
+}
+
 /// Updates participant without committing accounts.
 /// Use this when you only need to update the participant state on the ephemeral rollup.
 #[allow(unused_variables)]
@@ -231,7 +618,21 @@ pub fn update_participant<'info>(
     league: Pubkey,
     user: Pubkey,
 ) -> Result<()> {
-    update_participant_logic(&mut ctx.accounts.participant, ctx.remaining_accounts)
+    update_participant_logic(&mut ctx.accounts.participant, ctx.remaining_accounts)?;
+
+    Ok(())
+}
+
+/// Updates a page of the participant's positions without committing accounts.
+/// Use this when the participant holds more positions than fit in one transaction.
+#[allow(unused_variables)]
+pub fn update_participant_page<'info>(
+    ctx: Context<'_, '_, 'info, 'info, UpdateParticipant<'info>>,
+    league: Pubkey,
+    user: Pubkey,
+    offset: u16,
+) -> Result<()> {
+    update_participant_page_logic(&mut ctx.accounts.participant, offset, ctx.remaining_accounts)
 }
 
 #[allow(unused_variables)]
@@ -257,14 +658,12 @@ pub fn update_and_commit_participant<'info>(
     // Update participant on ER
     update_participant_logic(&mut ctx.accounts.participant, ctx.remaining_accounts)?;
 
-    // Collect accounts to commit (participant + all positions)
+    // Collect accounts to commit (participant + all passed positions, including the liquidated ones)
     let participant_account_info = ctx.accounts.participant.to_account_info();
     let mut committing_accounts: Vec<&AccountInfo<'info>> = vec![&participant_account_info];
 
-    let position_count = ctx.accounts.participant.positions.len();
-    for i in 0..position_count {
-        let position_ai = &ctx.remaining_accounts[i * 2];
-        committing_accounts.push(position_ai);
+    for i in 0..ctx.remaining_accounts.len() / ACCOUNTS_PER_POSITION {
+        committing_accounts.push(&ctx.remaining_accounts[i * ACCOUNTS_PER_POSITION]);
     }
 
     // Commit accounts
@@ -323,6 +722,77 @@ pub fn update_and_commit_participant<'info>(
     Ok(())
 }
 
+/// Permissionless liquidation (on ER).
+/// Any registered keeper can mark an under-margined participant to market. The keeper is credited
+/// a share of the liquidation penalty as reward points, kept apart from every league's equity.
+#[allow(unused_variables)]
+pub fn liquidate_participant<'info>(
+    ctx: Context<'_, '_, 'info, 'info, LiquidateParticipant<'info>>,
+    league: Pubkey,
+    user: Pubkey,
+) -> Result<()> {
+    let keeper = ctx.accounts.keeper.key();
+    require!(keeper != user, crate::errors::ErrorCode::InvalidKeeper);
+
+    let summary = update_participant_logic(&mut ctx.accounts.participant, ctx.remaining_accounts)?;
+    require!(summary.positions_liquidated > 0, crate::errors::ErrorCode::NotLiquidatable);
+
+    let keeper_reward = credit_keeper_reward(&mut ctx.accounts.keeper_account, summary.total_penalty)?;
+
+    // Commit the liquidated participant, its positions and the keeper account
+    let participant_account_info = ctx.accounts.participant.to_account_info();
+    let keeper_account_info = ctx.accounts.keeper_account.to_account_info();
+    let mut committing_accounts: Vec<&AccountInfo<'info>> =
+        vec![&participant_account_info, &keeper_account_info];
+    for i in 0..ctx.remaining_accounts.len() / ACCOUNTS_PER_POSITION {
+        committing_accounts.push(&ctx.remaining_accounts[i * ACCOUNTS_PER_POSITION]);
+    }
+
+    commit_accounts(
+        &ctx.accounts.keeper,
+        committing_accounts,
+        &ctx.accounts.magic_context.to_account_info(),
+        &ctx.accounts.magic_program.to_account_info(),
+    )?;
+
+    emit!(ParticipantLiquidated {
+        league,
+        user,
+        keeper,
+        positions_liquidated: summary.positions_liquidated,
+        total_penalty: summary.total_penalty,
+        keeper_reward,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    msg!(
+        "Participant {:?} liquidated by {:?}: {} positions, penalty {}, keeper reward {}",
+        user,
+        keeper,
+        summary.positions_liquidated,
+        summary.total_penalty,
+        keeper_reward
+    );
+
+    Ok(())
+}
+
+/// Credits the keeper its share of a liquidation's penalty, paid in reward points.
+/// Returns the reward.
+fn credit_keeper_reward(keeper_account: &mut Keeper, total_penalty: i64) -> Result<i64> {
+    let keeper_reward = calculate_bps_share(total_penalty, KEEPER_REWARD_BPS, Rounding::Down)?;
+    keeper_account.reward_points = keeper_account
+        .reward_points
+        .checked_add(keeper_reward)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    keeper_account.liquidations = keeper_account
+        .liquidations
+        .checked_add(1)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    Ok(keeper_reward)
+}
+
 #[delegate]
 #[derive(Accounts)]
 pub struct DelegateParticipant<'info> {
@@ -377,5 +847,306 @@ pub struct UpdateParticipant<'info> {
     pub program_id: AccountInfo<'info>,
 
     // Remaining accounts:
-    // [position_index_0, price_feed_index_0, position_index_1, price_feed_index_1, ...]
+    // [position_0, market_0, price_feed_0, position_1, market_1, price_feed_1, ...]
+}
+
+#[commit]
+#[derive(Accounts)]
+#[instruction(league: Pubkey, user: Pubkey)]
+pub struct LiquidateParticipant<'info> {
+    #[account(
+        mut,
+        seeds = [PARTICIPANT_SEED, league.as_ref(), user.as_ref()],
+        bump
+    )]
+    pub participant: Account<'info, Participant>,
+
+    #[account(
+        mut,
+        seeds = [KEEPER_SEED, keeper.key().as_ref()],
+        bump = keeper_account.bump
+    )]
+    pub keeper_account: Account<'info, Keeper>,
+
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    // Remaining accounts:
+    // [position_0, market_0, price_feed_0, position_1, market_1, price_feed_1, ...]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::PYTH_RECEIVER_PROGRAM_ID;
+    use crate::test_fixtures::{self, TestAccount, NOW, USD};
+
+    /// Position, market and price feed accounts of a position, with the oracle at `price` dollars.
+    fn priced_position(position: &Position, price: i64) -> Vec<TestAccount> {
+        let mut market = test_fixtures::market();
+        market.price_feed = position.price_feed;
+        let price_update = test_fixtures::price_update(market.feed_id, price * 100_000_000);
+
+        vec![
+            TestAccount::new(Pubkey::new_unique(), crate::ID, position),
+            TestAccount::new(position.market, crate::ID, &market),
+            TestAccount::new(position.price_feed, PYTH_RECEIVER_PROGRAM_ID, &price_update),
+        ]
+    }
+
+    fn position_keys(accounts: &[TestAccount]) -> Vec<Pubkey> {
+        accounts.iter().step_by(ACCOUNTS_PER_POSITION).map(|account| account.key).collect()
+    }
+
+    fn load_position(accounts: &[TestAccount], index: usize) -> Position {
+        Position::try_deserialize(&mut &accounts[index * ACCOUNTS_PER_POSITION].data[..]).unwrap()
+    }
+
+    /// Runs `update_participant_logic`, returning the updated participant.
+    fn update(participant: &Participant, accounts: &mut [TestAccount]) -> (Result<LiquidationSummary>, Participant) {
+        test_fixtures::use_clock();
+        let mut participant_account = TestAccount::new(Pubkey::new_unique(), crate::ID, participant);
+        let participant_info = participant_account.info();
+        let mut participant = Account::<Participant>::try_from(&participant_info).unwrap();
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+
+        let result = update_participant_logic(&mut participant, &infos);
+        (result, (*participant).clone())
+    }
+
+    /// $200 balance with 10 SOL long at $150, 10x cross: $150 margin locked.
+    fn cross_long(price: i64) -> (Participant, Vec<TestAccount>) {
+        let position = test_fixtures::position(Direction::Long, MarginMode::Cross, 150 * USD, 10_000_000);
+        let accounts = priced_position(&position, price);
+        let mut participant = test_fixtures::participant(200 * USD);
+        participant.used_margin = 150 * USD;
+        participant.positions = position_keys(&accounts);
+        (participant, accounts)
+    }
+
+    #[test]
+    fn cross_equity_above_maintenance_margin_is_not_liquidated() {
+        // -$150 PnL: $50 cross equity against $33.75 maintenance margin on $1,350
+        let (participant, mut accounts) = cross_long(135);
+
+        let (summary, participant) = update(&participant, &mut accounts);
+
+        assert_eq!(summary.unwrap().positions_liquidated, 0);
+        assert_eq!(participant.unrealized_pnl, -150 * USD);
+        assert_eq!(participant.used_margin, 150 * USD);
+        assert_eq!(participant.positions.len(), 1);
+        assert_eq!(load_position(&accounts, 0).unrealized_pnl, -150 * USD);
+    }
+
+    #[test]
+    fn cross_equity_below_maintenance_margin_liquidates_cross_positions() {
+        // -$180 PnL: $20 cross equity against $33 maintenance margin on $1,320
+        let (participant, mut accounts) = cross_long(132);
+
+        let (summary, participant) = update(&participant, &mut accounts);
+        let summary = summary.unwrap();
+
+        // 1% penalty on the $1,320 closed value
+        let penalty = 13_200_000;
+        assert_eq!(summary.positions_liquidated, 1);
+        assert_eq!(summary.total_penalty, penalty);
+        assert_eq!(participant.virtual_balance, 20 * USD - penalty);
+        assert_eq!(participant.total_liquidation_penalty, penalty);
+        assert_eq!(participant.unrealized_pnl, 0);
+        assert_eq!(participant.used_margin, 0);
+        assert!(participant.positions.is_empty());
+
+        let position = load_position(&accounts, 0);
+        assert_eq!(position.size, 0);
+        assert_eq!(position.closed_pnl, -180 * USD);
+        assert_eq!(position.closed_price, 132 * USD);
+        assert_eq!(position.liquidation_penalty, penalty);
+        assert_eq!(position.closed_at, NOW);
+    }
+
+    #[test]
+    fn remaining_accounts_must_cover_every_position() {
+        let (participant, mut accounts) = cross_long(150);
+        accounts.pop();
+
+        let (result, _) = update(&participant, &mut accounts);
+        assert_eq!(
+            result.err().unwrap(),
+            crate::errors::ErrorCode::InvalidUpdateParticipantRemainingAccounts.into()
+        );
+    }
+
+    /// Runs `update_participant_page_logic` for one page, returning the updated participant.
+    fn update_page(participant: &Participant, offset: u16, accounts: &mut [TestAccount]) -> (Result<()>, Participant) {
+        test_fixtures::use_clock();
+        let mut participant_account = TestAccount::new(Pubkey::new_unique(), crate::ID, participant);
+        let participant_info = participant_account.info();
+        let mut participant = Account::<Participant>::try_from(&participant_info).unwrap();
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+
+        let result = update_participant_page_logic(&mut participant, offset, &infos);
+        (result, (*participant).clone())
+    }
+
+    /// Two cross longs of `size` SOL at $150, 10x, one page each, with $10 reserved for an open order.
+    fn two_page_cross_longs(balance: i64, size: i64, price: i64) -> (Participant, Vec<TestAccount>) {
+        let position = test_fixtures::position(Direction::Long, MarginMode::Cross, 150 * USD, size);
+        let mut accounts = priced_position(&position, price);
+        accounts.extend(priced_position(&position, price));
+        let mut participant = test_fixtures::participant(balance);
+        participant.reserved_margin = 10 * USD;
+        participant.used_margin = 2 * calculate_required_margin(position.notional, position.leverage).unwrap() + 10 * USD;
+        participant.positions = position_keys(&accounts);
+        (participant, accounts)
+    }
+
+    #[test]
+    fn pages_of_a_pass_are_sent_in_order() {
+        let (participant, mut accounts) = two_page_cross_longs(10_000 * USD, 2_000_000, 140);
+        let (first_page, second_page) = accounts.split_at_mut(ACCOUNTS_PER_POSITION);
+
+        let (result, participant) = update_page(&participant, 0, first_page);
+        result.unwrap();
+        assert_eq!(participant.page_offset, 1);
+        assert_eq!(participant.unrealized_pnl, -20 * USD);
+
+        // Only the next page or a new pass is accepted
+        let (result, _) = update_page(&participant, 2, second_page);
+        assert_eq!(result.unwrap_err(), crate::errors::ErrorCode::InvalidPageOffset.into());
+
+        let (result, participant) = update_page(&participant, 1, second_page);
+        result.unwrap();
+        assert_eq!(participant.page_offset, 0);
+        assert_eq!(participant.unrealized_pnl, -40 * USD);
+        // Margin of both pages plus the order reservation
+        assert_eq!(participant.used_margin, 70 * USD);
+        assert!(!participant.page_liquidating);
+
+        // A repeated pass applies each position's PnL change once
+        let (first_page, _) = accounts.split_at_mut(ACCOUNTS_PER_POSITION);
+        let (result, participant) = update_page(&participant, 0, first_page);
+        result.unwrap();
+        assert_eq!(participant.unrealized_pnl, -40 * USD);
+    }
+
+    #[test]
+    fn breached_pass_liquidates_cross_positions_on_the_next_pass() {
+        // -$180 PnL: $20 cross equity against $33 maintenance margin on $1,320
+        let (participant, mut accounts) = two_page_cross_longs(200 * USD, 5_000_000, 132);
+
+        let (first_page, second_page) = accounts.split_at_mut(ACCOUNTS_PER_POSITION);
+        let (result, participant) = update_page(&participant, 0, first_page);
+        result.unwrap();
+        let (result, participant) = update_page(&participant, 1, second_page);
+        result.unwrap();
+
+        // Flagged, and the order reservation is voided so it can't fill meanwhile
+        assert!(participant.page_liquidating);
+        assert_eq!(participant.reserved_margin, 0);
+        assert_eq!(participant.reservation_epoch, 1);
+        assert_eq!(participant.used_margin, 150 * USD);
+        assert_eq!(participant.positions.len(), 2);
+        assert_eq!(load_position(&accounts, 0).size, 5_000_000);
+
+        // Liquidated positions leave `positions`, so every page of the pass starts at offset 0
+        let (first_page, second_page) = accounts.split_at_mut(ACCOUNTS_PER_POSITION);
+        let (result, participant) = update_page(&participant, 0, first_page);
+        result.unwrap();
+        assert_eq!(participant.positions.len(), 1);
+        assert_eq!(participant.page_offset, 0);
+        let (result, participant) = update_page(&participant, 0, second_page);
+        result.unwrap();
+
+        // 1% penalty on each $660 closed value
+        let penalty = 2 * 6_600_000;
+        assert!(!participant.page_liquidating);
+        assert!(participant.positions.is_empty());
+        assert_eq!(participant.virtual_balance, 20 * USD - penalty);
+        assert_eq!(participant.total_liquidation_penalty, penalty);
+        assert_eq!(participant.unrealized_pnl, 0);
+        assert_eq!(participant.used_margin, 0);
+        assert_eq!(load_position(&accounts, 1).closed_pnl, -90 * USD);
+    }
+
+    /// $10,000 balance with 2 SOL long at $150, 10x isolated: $30 margin locked.
+    fn isolated_long(price: i64) -> (Participant, Vec<TestAccount>) {
+        let position = test_fixtures::position(Direction::Long, MarginMode::Isolated, 150 * USD, 2_000_000);
+        let accounts = priced_position(&position, price);
+        let mut participant = test_fixtures::participant(10_000 * USD);
+        participant.used_margin = 30 * USD;
+        participant.positions = position_keys(&accounts);
+        (participant, accounts)
+    }
+
+    #[test]
+    fn isolated_position_above_liquidation_price_stays_open() {
+        let (participant, mut accounts) = isolated_long(139);
+
+        let (summary, participant) = update(&participant, &mut accounts);
+
+        assert_eq!(summary.unwrap().positions_liquidated, 0);
+        assert_eq!(participant.unrealized_pnl, -22 * USD);
+        assert_eq!(participant.positions.len(), 1);
+        let position = load_position(&accounts, 0);
+        assert_eq!(position.liquidation_price, 138_461_539);
+        assert_eq!(position.size, 2_000_000);
+    }
+
+    #[test]
+    fn isolated_position_past_liquidation_price_is_liquidated_alone() {
+        let (mut participant, mut accounts) = isolated_long(138);
+        // A healthy cross position on the same account is left open
+        let cross = test_fixtures::position(Direction::Short, MarginMode::Cross, 150 * USD, 1_000_000);
+        accounts.extend(priced_position(&cross, 138));
+        participant.used_margin += 15 * USD;
+        participant.positions = position_keys(&accounts);
+
+        let (summary, participant) = update(&participant, &mut accounts);
+        let summary = summary.unwrap();
+
+        // -$24 realized, 1% penalty on the $276 closed value
+        let penalty = 2_760_000;
+        assert_eq!(summary.positions_liquidated, 1);
+        assert_eq!(summary.total_penalty, penalty);
+        assert_eq!(participant.virtual_balance, 10_000 * USD - 24 * USD - penalty);
+        assert_eq!(participant.unrealized_pnl, 12 * USD);
+        assert_eq!(participant.used_margin, 15 * USD);
+        assert_eq!(participant.positions, vec![accounts[ACCOUNTS_PER_POSITION].key]);
+
+        assert_eq!(load_position(&accounts, 0).size, 0);
+        assert_eq!(load_position(&accounts, 1).size, 1_000_000);
+    }
+
+    #[test]
+    fn isolated_loss_and_penalty_are_capped_at_the_position_margin() {
+        let (participant, mut accounts) = isolated_long(100);
+
+        let (summary, participant) = update(&participant, &mut accounts);
+
+        // -$100 PnL, but only the $30 margin is lost and nothing is left for the penalty
+        assert_eq!(summary.unwrap().total_penalty, 0);
+        assert_eq!(participant.virtual_balance, 9_970 * USD);
+        assert_eq!(participant.used_margin, 0);
+        assert_eq!(load_position(&accounts, 0).closed_pnl, -30 * USD);
+    }
+
+    #[test]
+    fn keeper_earns_half_the_liquidation_penalty() {
+        let (participant, mut accounts) = cross_long(132);
+        let (summary, _) = update(&participant, &mut accounts);
+        let mut keeper = Keeper {
+            authority: Pubkey::new_unique(),
+            liquidations: 0,
+            reward_points: 0,
+            bump: 255,
+        };
+
+        let reward = credit_keeper_reward(&mut keeper, summary.unwrap().total_penalty).unwrap();
+        assert_eq!(reward, 6_600_000);
+
+        // Points accumulate across liquidations, rounded down
+        credit_keeper_reward(&mut keeper, 3).unwrap();
+        assert_eq!(keeper.reward_points, 6_600_001);
+        assert_eq!(keeper.liquidations, 2);
+    }
 }
@@ -3,8 +3,11 @@ use ephemeral_rollups_sdk::anchor::ephemeral;
 
 mod constants;
 mod errors;
+mod events;
 mod instructions;
 mod state;
+#[cfg(test)]
+mod test_fixtures;
 mod utils;
 
 declare_id!("V1fxrKvUB7ebNyhe8R7tYiPLYSNsicWwowyY6pbYrxM");
@@ -34,10 +37,27 @@ pub mod tdf_program {
     pub fn create_market(
         ctx: Context<CreateMarket>,
         symbol: [u8; 16],
+        feed_id: [u8; 32],
         decimals: u8,
         max_leverage: u8,
+        max_price_age_secs: u64,
+        max_conf_bps: u16,
+        maintenance_margin_bps: u16,
+        taker_fee_bps: u16,
+        half_spread_bps: u16,
     ) -> Result<()> {
-        instructions::create_market(ctx, symbol, decimals, max_leverage)
+        instructions::create_market(
+            ctx,
+            symbol,
+            feed_id,
+            decimals,
+            max_leverage,
+            max_price_age_secs,
+            max_conf_bps,
+            maintenance_margin_bps,
+            taker_fee_bps,
+            half_spread_bps,
+        )
     }
 
     pub fn update_market(
@@ -46,8 +66,24 @@ pub mod tdf_program {
         decimals: u8,
         is_active: bool,
         max_leverage: u8,
+        max_price_age_secs: u64,
+        max_conf_bps: u16,
+        maintenance_margin_bps: u16,
+        taker_fee_bps: u16,
+        half_spread_bps: u16,
     ) -> Result<()> {
-        instructions::update_market(ctx, symbol, decimals, is_active, max_leverage)
+        instructions::update_market(
+            ctx,
+            symbol,
+            decimals,
+            is_active,
+            max_leverage,
+            max_price_age_secs,
+            max_conf_bps,
+            maintenance_margin_bps,
+            taker_fee_bps,
+            half_spread_bps,
+        )
     }
 
     pub fn delete_market(ctx: Context<DeleteMarket>) -> Result<()> {
@@ -62,10 +98,16 @@ pub mod tdf_program {
         virtual_on_deposit: i64,
         start_ts: i64,
         end_ts: i64,
+        late_join_cutoff_ts: i64,
         metadata_uri: String,
         max_participants: u32,
+        min_participants: u32,
         max_leverage: u8,
         k: u16,
+        payout_schedule: crate::state::PayoutSchedule,
+        position_mode: crate::state::PositionMode,
+        taker_fee_bps: u16,
+        half_spread_bps: u16,
     ) -> Result<()> {
         instructions::create_league(
             ctx,
@@ -75,10 +117,16 @@ pub mod tdf_program {
             virtual_on_deposit,
             start_ts,
             end_ts,
+            late_join_cutoff_ts,
             metadata_uri,
             max_participants,
+            min_participants,
             max_leverage,
             k,
+            payout_schedule,
+            position_mode,
+            taker_fee_bps,
+            half_spread_bps,
         )
     }
 
@@ -90,10 +138,22 @@ pub mod tdf_program {
         instructions::close_league(ctx)
     }
 
+    pub fn cancel_league(ctx: Context<CancelLeague>) -> Result<()> {
+        instructions::cancel_league(ctx)
+    }
+
     pub fn join_league(ctx: Context<JoinLeague>) -> Result<()> {
         instructions::join_league(ctx)
     }
 
+    pub fn claim_reward(ctx: Context<ClaimReward>) -> Result<()> {
+        instructions::claim_reward(ctx)
+    }
+
+    pub fn refund_entry(ctx: Context<RefundEntry>) -> Result<()> {
+        instructions::refund_entry(ctx)
+    }
+
     pub fn delegate_participant(ctx: Context<DelegateParticipant>, league: Pubkey) -> Result<()> {
         instructions::delegate_participant(ctx, league)
     }
@@ -109,16 +169,8 @@ pub mod tdf_program {
         ctx: Context<InitUnopenedPosition>,
         league: Pubkey,
         current_position_seq: u64,
-        market: Pubkey,
-        market_decimals: u8,
     ) -> Result<()> {
-        instructions::init_unopened_position(
-            ctx,
-            league,
-            current_position_seq,
-            market,
-            market_decimals,
-        )
+        instructions::init_unopened_position(ctx, league, current_position_seq)
     }
 
     // pub fn create_position_permission(
@@ -139,18 +191,93 @@ pub mod tdf_program {
         instructions::delegate_unopened_position(ctx, participant, position_seq)
     }
 
-    pub fn open_position(
-        ctx: Context<OpenPosition>,
+    pub fn open_position<'info>(
+        ctx: Context<'_, '_, 'info, 'info, OpenPosition<'info>>,
         position_seq: u64,
         direction: crate::state::Direction,
         size: i64,
         leverage: u8,
+        margin_mode: crate::state::MarginMode,
+        stop_loss_price: i64,
+        take_profit_price: i64,
     ) -> Result<()> {
-        instructions::open_position(ctx, position_seq, direction, size, leverage)
+        instructions::open_position(
+            ctx,
+            position_seq,
+            direction,
+            size,
+            leverage,
+            margin_mode,
+            stop_loss_price,
+            take_profit_price,
+        )
+    }
+
+    pub fn increase_position(
+        ctx: Context<IncreasePosition>,
+        position_seq: u64,
+        size: i64,
+    ) -> Result<()> {
+        instructions::increase_position(ctx, position_seq, size)
+    }
+
+    pub fn update_position_triggers(
+        ctx: Context<UpdatePositionTriggers>,
+        position_seq: u64,
+        stop_loss_price: i64,
+        take_profit_price: i64,
+    ) -> Result<()> {
+        instructions::update_position_triggers(ctx, position_seq, stop_loss_price, take_profit_price)
+    }
+
+    pub fn execute_trigger(ctx: Context<ExecuteTrigger>, user: Pubkey, position_seq: u64) -> Result<()> {
+        instructions::execute_trigger(ctx, user, position_seq)
+    }
+
+    pub fn init_order(ctx: Context<InitOrder>, league: Pubkey, order_seq: u64) -> Result<()> {
+        instructions::init_order(ctx, league, order_seq)
+    }
+
+    pub fn delegate_order(ctx: Context<DelegateOrder>, order_seq: u64) -> Result<()> {
+        instructions::delegate_order(ctx, order_seq)
+    }
+
+    pub fn place_order(
+        ctx: Context<PlaceOrder>,
+        order_seq: u64,
+        direction: crate::state::Direction,
+        size: i64,
+        leverage: u8,
+        margin_mode: crate::state::MarginMode,
+        limit_price: i64,
+        expiry_ts: i64,
+    ) -> Result<()> {
+        instructions::place_order(
+            ctx,
+            order_seq,
+            direction,
+            size,
+            leverage,
+            margin_mode,
+            limit_price,
+            expiry_ts,
+        )
     }
 
-    pub fn close_position(ctx: Context<ClosePosition>, position_seq: u64) -> Result<()> {
-        instructions::close_position(ctx, position_seq)
+    pub fn fill_order(ctx: Context<FillOrder>, user: Pubkey, order_seq: u64) -> Result<()> {
+        instructions::fill_order(ctx, user, order_seq)
+    }
+
+    pub fn cancel_order(ctx: Context<CancelOrder>, user: Pubkey, order_seq: u64) -> Result<()> {
+        instructions::cancel_order(ctx, user, order_seq)
+    }
+
+    pub fn close_position(
+        ctx: Context<ClosePosition>,
+        position_seq: u64,
+        close_size: i64,
+    ) -> Result<()> {
+        instructions::close_position(ctx, position_seq, close_size)
     }
 
     pub fn commit_position(
@@ -162,6 +289,23 @@ pub mod tdf_program {
         instructions::commit_position(ctx, league, user, position_seq)
     }
 
+    pub fn undelegate_position(
+        ctx: Context<CommitPosition>,
+        league: Pubkey,
+        user: Pubkey,
+        position_seq: u64,
+    ) -> Result<()> {
+        instructions::undelegate_position(ctx, league, user, position_seq)
+    }
+
+    pub fn close_position_account(
+        ctx: Context<ClosePositionAccount>,
+        league: Pubkey,
+        position_seq: u64,
+    ) -> Result<()> {
+        instructions::close_position_account(ctx, league, position_seq)
+    }
+
     pub fn update_participant<'info>(
         ctx: Context<'_, '_, 'info, 'info, UpdateParticipant<'info>>,
         league: Pubkey,
@@ -170,6 +314,23 @@ pub mod tdf_program {
         instructions::update_participant(ctx, league, user)
     }
 
+    pub fn update_participant_page<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateParticipant<'info>>,
+        league: Pubkey,
+        user: Pubkey,
+        offset: u16,
+    ) -> Result<()> {
+        instructions::update_participant_page(ctx, league, user, offset)
+    }
+
+    pub fn expand_participant_positions(
+        ctx: Context<ExpandParticipantPositions>,
+        league: Pubkey,
+        new_max_positions: u16,
+    ) -> Result<()> {
+        instructions::expand_participant_positions(ctx, league, new_max_positions)
+    }
+
     pub fn commit_participant(
         ctx: Context<UpdateParticipant>,
         league: Pubkey,
@@ -186,6 +347,26 @@ pub mod tdf_program {
         instructions::update_and_commit_participant(ctx, league, user)
     }
 
+    pub fn init_keeper(ctx: Context<InitKeeper>) -> Result<()> {
+        instructions::init_keeper(ctx)
+    }
+
+    pub fn delegate_keeper(ctx: Context<DelegateKeeper>) -> Result<()> {
+        instructions::delegate_keeper(ctx)
+    }
+
+    pub fn undelegate_keeper(ctx: Context<UndelegateKeeper>) -> Result<()> {
+        instructions::undelegate_keeper(ctx)
+    }
+
+    pub fn liquidate_participant<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LiquidateParticipant<'info>>,
+        league: Pubkey,
+        user: Pubkey,
+    ) -> Result<()> {
+        instructions::liquidate_participant(ctx, league, user)
+    }
+
     pub fn update_leaderboard_with_participant(
         ctx: Context<UpdateLeaderboardWithParticipant>,
     ) -> Result<()> {
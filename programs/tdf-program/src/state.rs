@@ -21,9 +21,15 @@ pub const GLOBAL_CONFIG_SPACE: usize = 8 + 32 + 2 + 32 + 1;
 pub struct Market {
     pub symbol: [u8; 16],   // e.g., "SOL/USDC"
     pub price_feed: Pubkey, // price oracle address
+    pub feed_id: [u8; 32],  // pyth feed id expected in the price feed account
     pub decimals: u8, // e.g., 8 for SOLUSD by pyth
     pub is_active: bool,
     pub max_leverage: u8, // e.g., 20
+    pub max_price_age_secs: u64, // reject oracle prices older than this
+    pub max_conf_bps: u16, // reject oracle prices with conf / price above this, e.g., 100 = 1%
+    pub maintenance_margin_bps: u16, // e.g., 250 = 2.5% of position value
+    pub taker_fee_bps: u16,   // fee on the notional of every virtual fill, e.g., 5 = 0.05%
+    pub half_spread_bps: u16, // synthetic half spread around the oracle price for market fills
     
     // metadata
     pub listed_by: Pubkey, // admin
@@ -33,13 +39,27 @@ pub struct Market {
 }
 
 pub const MARKET_SEED: &[u8] = b"market";
-pub const MARKET_SPACE: usize = 8 + 16 + 32 + 1 + 1 + 1 + 32 + 8 + 1;
+pub const MARKET_SPACE: usize = 8 + 16 + 32 + 32 + 1 + 1 + 1 + 8 + 2 + 2 + 2 + 2 + 32 + 8 + 1;
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
 pub enum LeagueStatus {
     Pending,
     Active,
     Closed,
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum PositionMode {
+    Hedge,  // independent positions per open, both directions on the same market
+    OneWay, // one net position per market, opposite opens reduce or flip it
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum PayoutSchedule {
+    Tiered { bps: Vec<u16> },      // fixed share per rank in bps, sums to 10_000, max length of 10
+    LinearDecay { ranks: u16 },    // top `ranks` paid, rank i weighted (ranks - i)
+    ProportionalPnl { ranks: u16 }, // top `ranks` paid pro rata to positive PnL
 }
 
 #[account]
@@ -55,14 +75,22 @@ pub struct League {
     pub entry_amount: i64,        // token amount to enter the league
     pub reward_vault: Pubkey, // SPL token vault for rewards. TODO: add RewardVault account struct for more tokens
     pub total_reward_amount: u64, // Total reward amount fixed at close_league
+    pub protocol_fee_amount: u64, // Protocol fee skimmed into the treasury at close_league
+    pub payout_schedule: PayoutSchedule, // How total_reward_amount is split across ranks
     pub virtual_on_deposit: i64, // Paper dollar (e.g., 10_000 * 1e6)
 
     // metadata
     pub metadata_uri: String, // URI to the league metadata
     pub start_ts: i64,        // timestamp
     pub end_ts: i64,          // timestamp
+    pub late_join_cutoff_ts: i64, // joins allowed after start until this timestamp, 0 if none
     pub max_participants: u32,
+    pub min_participants: u32, // league can be cancelled if not met by start_ts
+    pub participant_count: u32, // number of participants joined
     pub max_leverage: u8, // e.g. 20x
+    pub position_mode: PositionMode,
+    pub taker_fee_bps: u16,   // overrides the market taker fee, 0xFFFF to use the market's
+    pub half_spread_bps: u16, // overrides the market half spread, 0xFFFF to use the market's
 
     pub bump: u8,
 }
@@ -79,11 +107,19 @@ pub const LEAGUE_SPACE: usize = 8
     + 32
     + 8
     + 8
+    + 8
+    + (1 + 4 + 2 * 10)
     + (4 + 200)
     + 8
     + 8
+    + 8
+    + 4
+    + 4
     + 4
     + 1
+    + 1
+    + 2
+    + 2
     + 1;
 
 #[account]
@@ -108,20 +144,32 @@ pub const LEADERBOARD_SPACE: usize =
 pub struct Participant {
     pub league: Pubkey,
     pub user: Pubkey,
-    pub claimed: bool, // if the user has claimed the reward
+    pub claimed: bool, // if the user has claimed the reward (or the entry refund if cancelled)
 
     // Realtime stats
     pub virtual_balance: i64, // Paper dollar (e.g., 10_000 * 1e6), only update when position is updated
     pub unrealized_pnl: i64,  // accumulated unrealized PnL, update with position checking cycle
     pub used_margin: i64, // used margin for current position, update with position is opened or updated
+    pub reserved_margin: i64, // margin reserved by open limit orders, included in used_margin
+    pub reservation_epoch: u32, // bumped when a liquidation releases every open order's reservation
 
     pub total_volume: i64, // accumulated volume, only update when position is opened or updated
+    pub total_liquidation_penalty: i64, // accumulated liquidation penalties
+    pub total_fees: i64, // accumulated taker fees paid on virtual fills
     pub topk_equity_index: u16, // TopK equity index if not in, 0xFFFF
     pub topk_volume_index: u16, // TopK volume index if not in, 0xFFFF
 
+    // Paged update in progress, see update_participant_page
+    pub page_offset: u16,             // index of the next position to update, 0 when no paged update is in progress
+    pub page_used_margin: i64,        // margin of the positions updated so far in this pass
+    pub page_cross_pnl: i64,          // cross PnL less isolated margin of the positions updated so far in this pass
+    pub page_maintenance_margin: i64, // cross maintenance margin of the positions updated so far in this pass
+    pub page_liquidating: bool,       // cross maintenance margin breached, the next pass liquidates every cross position
+
     // Position tracking sequence number
     pub current_position_seq: u64, // sequence number of current position
-    pub positions: Vec<Pubkey>,    // position accounts, max length is 10
+    pub max_positions: u16,        // capacity of positions, grows with expand_participant_positions
+    pub positions: Vec<Pubkey>,    // position accounts, max length is max_positions
 
     pub bump: u8,
 }
@@ -135,11 +183,36 @@ impl Participant {
     pub fn available_balance(&self) -> i64 {
         self.equity() - self.used_margin
     }
+
+    // Restarts a paged update in progress once positions are added or removed,
+    // since its offset and running totals no longer line up with `positions`
+    pub fn reset_page(&mut self) {
+        self.page_offset = 0;
+    }
 }
 
 pub const PARTICIPANT_SEED: &[u8] = b"participant";
-pub const PARTICIPANT_SPACE: usize =
-    8 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 2 + 2 + 8 + (4 + 32 * 10) + 1;
+pub const DEFAULT_MAX_POSITIONS: u16 = 10;
+// A full update or liquidation must fit in one transaction (64 accounts, via a lookup table):
+// 32 positions + up to 10 markets and 10 price feeds + the instruction's own accounts
+pub const MAX_POSITIONS_LIMIT: u16 = 32;
+pub const PARTICIPANT_SPACE: usize = participant_space(DEFAULT_MAX_POSITIONS);
+
+pub const fn participant_space(max_positions: u16) -> usize {
+    8 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 4 + 8 + 8 + 8 + 2 + 2 + (2 + 8 + 8 + 8 + 1) + 8 + 2 + (4 + 32 * max_positions as usize) + 1
+}
+
+#[account]
+pub struct Keeper {
+    pub authority: Pubkey,
+    pub liquidations: u64,  // number of participants liquidated
+    pub reward_points: i64, // paper dollars earned from liquidation penalties, never counted in league equity
+
+    pub bump: u8,
+}
+
+pub const KEEPER_SEED: &[u8] = b"keeper";
+pub const KEEPER_SPACE: usize = 8 + 32 + 8 + 8 + 1;
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
 pub enum Direction {
@@ -147,6 +220,12 @@ pub enum Direction {
     Short = -1,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum MarginMode {
+    Cross,    // margin shared with the participant's other cross positions
+    Isolated, // loss capped at the position's own margin
+}
+
 #[account]
 pub struct Position {
     pub league: Pubkey,
@@ -160,6 +239,10 @@ pub struct Position {
     pub entry_price: i64, // average price in price-decimal (1e6)
     pub entry_size: i64,  // token amount of entry size
     pub leverage: u8,     // e.g. 5x
+    pub margin_mode: MarginMode,
+    pub liquidation_price: i64, // price in price-decimal (1e6), 0 for cross margin
+    pub stop_loss_price: i64,   // price in price-decimal (1e6), 0 if not set
+    pub take_profit_price: i64, // price in price-decimal (1e6), 0 if not set
 
     // Realtime stats
     pub size: i64,           // token amount of current position
@@ -173,13 +256,41 @@ pub struct Position {
     pub closed_price: i64,  // price in price-decimal (1e6)
     pub closed_equity: i64, // closed_price * size (1e6)
     pub closed_pnl: i64,    // (closed_notional - notional) * direction
+    pub liquidation_penalty: i64, // penalty charged if the position was liquidated
 
     pub bump: u8,
 }
 
 pub const POSITION_SEED: &[u8] = b"position";
 pub const POSITION_SPACE: usize =
-    8 + (32 + 32 + 32 + 1 + 32 + 8) + (1 + 8 + 8 + 1) + 8 * 5 + 8 * 4 + 1;
+    8 + (32 + 32 + 32 + 1 + 32 + 8) + (1 + 8 + 8 + 1 + 1 + 8 + 8 + 8) + 8 * 5 + 8 * 5 + 1;
+
+#[account]
+pub struct Order {
+    pub league: Pubkey,
+    pub user: Pubkey,
+    pub market: Pubkey,
+    pub seq_num: u64, // shares the position sequence, filled into the position with the same seq_num
+
+    pub direction: Direction,
+    pub size: i64,        // token amount to open
+    pub leverage: u8,     // e.g. 5x
+    pub margin_mode: MarginMode,
+    pub limit_price: i64,     // price in price-decimal (1e6)
+    pub reserved_margin: i64, // margin reserved at the limit price
+    pub reservation_epoch: u32, // participant's reservation epoch at placement, stale once released by liquidation
+    pub expiry_ts: i64,       // 0 if good till cancelled
+
+    pub placed_at: i64,
+    pub filled_at: i64,
+    pub cancelled_at: i64,
+
+    pub bump: u8,
+}
+
+pub const ORDER_SEED: &[u8] = b"order";
+pub const ORDER_SPACE: usize =
+    8 + (32 + 32 + 32 + 8) + (1 + 8 + 1 + 1 + 8 + 8 + 4 + 8) + 8 * 3 + 1;
 
 #[account]
 pub struct PrivateResourceExample {
@@ -0,0 +1,220 @@
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::SUCCESS;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use pyth_solana_receiver_sdk::price_update::{PriceFeedMessage, PriceUpdateV2, VerificationLevel};
+
+use crate::state::{
+    Direction, Leaderboard, League, LeagueStatus, MarginMode, Market, Order, Participant,
+    PayoutSchedule, Position, PositionMode, DEFAULT_MAX_POSITIONS,
+};
+
+pub const USD: i64 = 1_000_000; // 1 paper dollar in QUOTE_DECIMALS
+pub const SOL_DECIMALS: u8 = 6;
+pub const NOW: i64 = 1_700_000_000; // unix timestamp returned by Clock::get in tests
+
+struct ClockStubs;
+
+impl SyscallStubs for ClockStubs {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: NOW,
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+}
+
+/// Lets `Clock::get` run off-chain, at `NOW`.
+pub fn use_clock() {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(ClockStubs));
+    });
+}
+
+/// Storage behind an `AccountInfo`, for code that loads accounts itself.
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+impl TestAccount {
+    pub fn new<T: AccountSerialize>(key: Pubkey, owner: Pubkey, account: &T) -> Self {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        Self { key, owner, lamports: 1, data }
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(&self.key, false, true, &mut self.lamports, &mut self.data, &self.owner, false, 0)
+    }
+}
+
+/// Fully verified Pyth update at exponent -8, published at `NOW` with a 0.01% confidence interval.
+pub fn price_update(feed_id: [u8; 32], price: i64) -> PriceUpdateV2 {
+    PriceUpdateV2 {
+        write_authority: Pubkey::new_unique(),
+        verification_level: VerificationLevel::Full,
+        price_message: PriceFeedMessage {
+            feed_id,
+            price,
+            conf: (price / 10_000) as u64,
+            exponent: -8,
+            publish_time: NOW,
+            prev_publish_time: NOW - 1,
+            ema_price: price,
+            ema_conf: 0,
+        },
+        posted_slot: 0,
+    }
+}
+
+/// SOL/USD market: 20x max, 2.5% maintenance margin, 5 bps fee and half spread.
+pub fn market() -> Market {
+    Market {
+        symbol: *b"SOL/USD\0\0\0\0\0\0\0\0\0",
+        price_feed: Pubkey::new_unique(),
+        feed_id: [7; 32],
+        decimals: SOL_DECIMALS,
+        is_active: true,
+        max_leverage: 20,
+        max_price_age_secs: 60,
+        max_conf_bps: 100,
+        maintenance_margin_bps: 250,
+        taker_fee_bps: 5,
+        half_spread_bps: 5,
+        listed_by: Pubkey::new_unique(),
+        created_at: 0,
+        bump: 255,
+    }
+}
+
+/// Closed league with a $10,000 paper deposit, using the market's fees.
+pub fn league(payout_schedule: PayoutSchedule, total_reward_amount: u64) -> League {
+    League {
+        id: "test".to_string(),
+        creator: Pubkey::new_unique(),
+        status: LeagueStatus::Closed,
+        markets: vec![],
+        leaderboard: Pubkey::new_unique(),
+        entry_token_mint: Pubkey::new_unique(),
+        entry_amount: 1_000_000,
+        reward_vault: Pubkey::new_unique(),
+        total_reward_amount,
+        protocol_fee_amount: 0,
+        payout_schedule,
+        virtual_on_deposit: 10_000 * USD,
+        metadata_uri: String::new(),
+        start_ts: 0,
+        end_ts: 0,
+        late_join_cutoff_ts: 0,
+        max_participants: 100,
+        min_participants: 1,
+        participant_count: 0,
+        max_leverage: 20,
+        position_mode: PositionMode::Hedge,
+        taker_fee_bps: 0xFFFF,
+        half_spread_bps: 0xFFFF,
+        bump: 255,
+    }
+}
+
+/// Leaderboard whose equity ranking holds the given scores, best first.
+pub fn leaderboard(topk_equity_scores: Vec<i64>) -> Leaderboard {
+    Leaderboard {
+        league: Pubkey::new_unique(),
+        k: 10,
+        topk_equity: topk_equity_scores.iter().map(|_| Pubkey::new_unique()).collect(),
+        topk_equity_scores,
+        topk_volume: vec![],
+        topk_volume_scores: vec![],
+        last_updated: 0,
+        bump: 255,
+    }
+}
+
+/// Fresh participant holding `virtual_balance` with no positions.
+pub fn participant(virtual_balance: i64) -> Participant {
+    Participant {
+        league: Pubkey::new_unique(),
+        user: Pubkey::new_unique(),
+        claimed: false,
+        virtual_balance,
+        unrealized_pnl: 0,
+        used_margin: 0,
+        reserved_margin: 0,
+        reservation_epoch: 0,
+        total_volume: 0,
+        total_liquidation_penalty: 0,
+        total_fees: 0,
+        topk_equity_index: 0xFFFF,
+        topk_volume_index: 0xFFFF,
+        page_offset: 0,
+        page_used_margin: 0,
+        page_cross_pnl: 0,
+        page_maintenance_margin: 0,
+        page_liquidating: false,
+        current_position_seq: 0,
+        max_positions: DEFAULT_MAX_POSITIONS,
+        positions: vec![],
+        bump: 255,
+    }
+}
+
+/// Open 10x position on the SOL/USD market at `entry_price` for `size`, opened at `NOW`.
+pub fn position(direction: Direction, margin_mode: MarginMode, entry_price: i64, size: i64) -> Position {
+    Position {
+        league: Pubkey::new_unique(),
+        user: Pubkey::new_unique(),
+        market: Pubkey::new_unique(),
+        market_decimals: SOL_DECIMALS,
+        price_feed: Pubkey::new_unique(),
+        seq_num: 1,
+        direction,
+        entry_price,
+        entry_size: size,
+        leverage: 10,
+        margin_mode,
+        liquidation_price: 0,
+        stop_loss_price: 0,
+        take_profit_price: 0,
+        size,
+        notional: crate::utils::calculate_notional(entry_price, size, SOL_DECIMALS).unwrap(),
+        unrealized_pnl: 0,
+        opened_at: NOW,
+        closed_at: 0,
+        closed_size: 0,
+        closed_price: 0,
+        closed_equity: 0,
+        closed_pnl: 0,
+        liquidation_penalty: 0,
+        bump: 255,
+    }
+}
+
+/// Good-till-cancelled 10x cross limit order on the SOL/USD market, placed at `NOW` without a reservation.
+pub fn order(direction: Direction, limit_price: i64, size: i64) -> Order {
+    Order {
+        league: Pubkey::new_unique(),
+        user: Pubkey::new_unique(),
+        market: Pubkey::new_unique(),
+        seq_num: 1,
+        direction,
+        size,
+        leverage: 10,
+        margin_mode: MarginMode::Cross,
+        limit_price,
+        reserved_margin: 0,
+        reservation_epoch: 0,
+        expiry_ts: 0,
+        placed_at: NOW,
+        filled_at: 0,
+        cancelled_at: 0,
+        bump: 255,
+    }
+}
@@ -1,34 +1,170 @@
 use anchor_lang::prelude::*;
-use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use pyth_solana_receiver_sdk::price_update::{PriceUpdateV2, VerificationLevel};
 
-use crate::state::Direction;
+use crate::constants::{
+    DELEGATION_PROGRAM_ID, ER_PRICE_PROGRAM_ID, PYTH_RECEIVER_PROGRAM_ID, QUOTE_DECIMALS, USE_MARKET_FEE,
+};
+use crate::state::{Direction, League, MarginMode, Market, Participant, Position, PARTICIPANT_SEED};
+
+pub fn get_price_and_exponent_from_pyth(
+    price_ai: &AccountInfo,
+    feed_id: &[u8; 32],
+    max_price_age_secs: u64,
+    max_conf_bps: u16,
+) -> Result<(i64, i32)> {
+    // Only accept price feeds written by the Pyth receiver or the ER price program
+    require!(
+        *price_ai.owner == PYTH_RECEIVER_PROGRAM_ID || *price_ai.owner == ER_PRICE_PROGRAM_ID,
+        crate::errors::ErrorCode::InvalidOraclePriceFeed
+    );
 
-pub fn get_price_and_exponent_from_pyth(price_ai: &AccountInfo) -> Result<(i64, i32)> {
     // Deserialize the price feed
     let data_ref = price_ai.data.borrow();
     let price_update = PriceUpdateV2::try_deserialize_unchecked(&mut data_ref.as_ref())
         .map_err(Into::<Error>::into)?;
 
-    // Reject if the update is older than 60 seconds : DEV
-    // let maximum_age: u64 = 60;
+    // The Pyth receiver also stores partially verified updates, only fully verified ones are trusted
+    if *price_ai.owner == PYTH_RECEIVER_PROGRAM_ID {
+        require!(
+            price_update.verification_level == VerificationLevel::Full,
+            crate::errors::ErrorCode::InsufficientOracleVerification
+        );
+    }
+
+    // The feed must be the one configured on the market
+    require!(
+        price_update.price_message.feed_id == *feed_id,
+        crate::errors::ErrorCode::OracleMismatch
+    );
+
+    let price = price_update.get_price_unchecked(feed_id)?;
+    require!(price.price > 0, crate::errors::ErrorCode::InvalidOraclePriceFeed);
 
-    // Feed id is the price_update account address
-    let feed_id: [u8; 32] = price_ai.key().to_bytes();
+    // Reject if the update is older than the market's maximum age
+    let now = Clock::get()?.unix_timestamp;
+    let age = now.saturating_sub(price.publish_time).max(0) as u64;
+    require!(
+        age <= max_price_age_secs,
+        crate::errors::ErrorCode::StaleOraclePrice
+    );
 
-    let price = price_update.get_price_unchecked(&feed_id)?;
+    // Reject if the confidence interval is too wide relative to the price
+    require!(
+        price.conf as u128 * 10_000 <= price.price as u128 * max_conf_bps as u128,
+        crate::errors::ErrorCode::OracleConfidenceTooWide
+    );
 
     Ok((price.price, price.exponent))
 }
 
-pub fn calculate_notional(price_in_decimal: i64, size: i64, decimals: u8) -> i64 {
-    let scale = 10i128.pow(decimals as u32);
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// Division with explicit rounding direction, denominator must be positive.
+pub fn div_with_rounding(numerator: i128, denominator: i128, rounding: Rounding) -> Result<i128> {
+    require!(denominator != 0, crate::errors::ErrorCode::DivisionByZero);
+    require!(denominator > 0, crate::errors::ErrorCode::MathOverflow);
+
+    // Euclidean division floors for a positive denominator
+    let quotient = numerator.div_euclid(denominator);
+    match rounding {
+        Rounding::Down => Ok(quotient),
+        Rounding::Up if numerator.rem_euclid(denominator) != 0 => quotient
+            .checked_add(1)
+            .ok_or(crate::errors::ErrorCode::MathOverflow.into()),
+        Rounding::Up => Ok(quotient),
+    }
+}
+
+/// Rounding of an oracle price for a trade, never in the trader's favour:
+/// buys (long entry, short exit) round up, sells (short entry, long exit) round down.
+/// This deliberately differs from rounding long entries down, which would hand longs a
+/// better entry than the oracle quoted; margin still rounds up in `calculate_required_margin`.
+pub fn price_rounding(is_buy: bool) -> Rounding {
+    if is_buy {
+        Rounding::Up
+    } else {
+        Rounding::Down
+    }
+}
+
+/// Rescales a Pyth price (price * 10^exponent) into QUOTE_DECIMALS.
+pub fn scale_price_to_quote_decimals(price: i64, exponent: i32, rounding: Rounding) -> Result<i64> {
+    let shift = exponent
+        .checked_add(QUOTE_DECIMALS as i32)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    let price128 = price as i128;
+
+    let scaled = if shift >= 0 {
+        let factor = 10i128
+            .checked_pow(shift as u32)
+            .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+        price128
+            .checked_mul(factor)
+            .ok_or(crate::errors::ErrorCode::MathOverflow)?
+    } else {
+        let divisor = 10i128
+            .checked_pow(shift.unsigned_abs())
+            .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+        div_with_rounding(price128, divisor, rounding)?
+    };
+
+    i64::try_from(scaled).map_err(|_| crate::errors::ErrorCode::MathOverflow.into())
+}
+
+/// Margin locked by a position: ceil(notional / leverage)
+pub fn calculate_required_margin(notional: i64, leverage: u8) -> Result<i64> {
+    let margin = div_with_rounding(notional as i128, leverage as i128, Rounding::Up)?;
+    i64::try_from(margin).map_err(|_| crate::errors::ErrorCode::MathOverflow.into())
+}
+
+/// Loads a participant account that may be delegated to the ER.
+/// On the base layer a delegated participant is owned by the delegation program,
+/// so the owner is checked against both before verifying the PDA of its league / user.
+pub fn load_participant(participant_ai: &AccountInfo, league: &Pubkey) -> Result<Participant> {
+    require!(
+        *participant_ai.owner == crate::ID || *participant_ai.owner == DELEGATION_PROGRAM_ID,
+        crate::errors::ErrorCode::InvalidParticipant
+    );
+
+    let participant = Participant::try_deserialize(&mut &participant_ai.data.borrow()[..])?;
+    let participant_pda = Pubkey::create_program_address(
+        &[PARTICIPANT_SEED, league.as_ref(), participant.user.as_ref(), &[participant.bump]],
+        &crate::ID,
+    )
+    .map_err(|_| crate::errors::ErrorCode::InvalidParticipant)?;
+    require_keys_eq!(participant_ai.key(), participant_pda, crate::errors::ErrorCode::InvalidParticipant);
+    require_keys_eq!(participant.league, *league, crate::errors::ErrorCode::InvalidParticipant);
+
+    Ok(participant)
+}
+
+/// Basis-point share of an amount: amount * bps / 10_000
+pub fn calculate_bps_share(amount: i64, bps: u16, rounding: Rounding) -> Result<i64> {
+    let numerator = (amount as i128)
+        .checked_mul(bps as i128)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    let share = div_with_rounding(numerator, 10_000, rounding)?;
+    i64::try_from(share).map_err(|_| crate::errors::ErrorCode::MathOverflow.into())
+}
+
+pub fn calculate_notional(price_in_decimal: i64, size: i64, decimals: u8) -> Result<i64> {
+    let scale = 10i128
+        .checked_pow(decimals as u32)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
     let price128_in_decimal = price_in_decimal as i128;
     let size128 = size as i128;
 
     // (price * size) / 10^decimals
-    let notional = (price128_in_decimal * size128) / scale;
+    let notional = price128_in_decimal
+        .checked_mul(size128)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?
+        / scale;
 
-    i64::try_from(notional).expect("notional overflow")
+    i64::try_from(notional).map_err(|_| crate::errors::ErrorCode::MathOverflow.into())
 }
 
 pub fn dir_sign(direction: Direction) -> i64 {
@@ -44,20 +180,355 @@ pub fn calculate_unrealized_pnl(
     size: i64,
     decimals: u8,
     direction: Direction,
-) -> i64 {
+) -> Result<i64> {
     let current_price128 = current_price as i128;
     let size128 = size as i128;
-    let scale = 10i128.pow(decimals as u32);
+    let scale = 10i128
+        .checked_pow(decimals as u32)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
     let notional128 = notional as i128;
     let dir128 = dir_sign(direction.clone()) as i128;
-    let pnl128 = (current_price128 * size128 / scale - notional128) * dir128;
-    i64::try_from(pnl128).expect("pnl overflow")
+    let current_notional128 = current_price128
+        .checked_mul(size128)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?
+        / scale;
+    let pnl128 = (current_notional128 - notional128) * dir128;
+    i64::try_from(pnl128).map_err(|_| crate::errors::ErrorCode::MathOverflow.into())
 }
 
-pub fn calculate_price_from_notional_and_size(notional: i64, size: i64, decimals: u8) -> i64 {
-    let scale = 10i128.pow(decimals as u32);
+pub fn calculate_price_from_notional_and_size(notional: i64, size: i64, decimals: u8) -> Result<i64> {
+    require!(size != 0, crate::errors::ErrorCode::DivisionByZero);
+
+    let scale = 10i128
+        .checked_pow(decimals as u32)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
     let notional128 = notional as i128;
     let size128 = size as i128;
-    let price128 = notional128 / size128 * scale;
-    i64::try_from(price128).expect("price overflow")
+
+    // notional * 10^decimals / size, multiply first to keep precision
+    let price128 = notional128
+        .checked_mul(scale)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?
+        / size128;
+    i64::try_from(price128).map_err(|_| crate::errors::ErrorCode::MathOverflow.into())
+}
+
+/// Price at which an isolated position's remaining margin equals its maintenance margin,
+/// at the market's current maintenance margin ratio.
+/// Returns 0 for cross margin positions, which are liquidated on the participant's equity instead.
+pub fn calculate_liquidation_price(position: &Position, maintenance_margin_bps: u16) -> Result<i64> {
+    if position.margin_mode == MarginMode::Cross || position.size == 0 {
+        return Ok(0);
+    }
+
+    let scale = 10i128
+        .checked_pow(position.market_decimals as u32)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    let notional128 = position.notional as i128;
+    let margin128 = calculate_required_margin(position.notional, position.leverage)? as i128;
+    let mm128 = maintenance_margin_bps as i128;
+
+    // Long:  margin + (value - notional) = value * mm  =>  value = (notional - margin) / (1 - mm)
+    // Short: margin + (notional - value) = value * mm  =>  value = (notional + margin) / (1 + mm)
+    // Rounded towards the entry price so liquidation never fires late
+    let (value_numerator, value_denominator, rounding) = match position.direction {
+        Direction::Long => (notional128 - margin128, 10_000 - mm128, Rounding::Up),
+        Direction::Short => (notional128 + margin128, 10_000 + mm128, Rounding::Down),
+    };
+
+    let numerator = value_numerator
+        .checked_mul(scale)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?
+        .checked_mul(10_000)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    let denominator = (position.size as i128)
+        .checked_mul(value_denominator)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    let price128 = div_with_rounding(numerator, denominator, rounding)?;
+
+    i64::try_from(price128).map_err(|_| crate::errors::ErrorCode::MathOverflow.into())
+}
+
+/// Taker fee and half spread in effect for a market within a league.
+pub struct TradingFees {
+    pub taker_fee_bps: u16,
+    pub half_spread_bps: u16,
+}
+
+/// League overrides take precedence over the market's settings unless left at 0xFFFF.
+pub fn resolve_trading_fees(league: &League, market: &Market) -> TradingFees {
+    let pick = |league_bps: u16, market_bps: u16| {
+        if league_bps == USE_MARKET_FEE { market_bps } else { league_bps }
+    };
+
+    TradingFees {
+        taker_fee_bps: pick(league.taker_fee_bps, market.taker_fee_bps),
+        half_spread_bps: pick(league.half_spread_bps, market.half_spread_bps),
+    }
+}
+
+/// Opening a long or closing a short buys, the rest sells.
+pub fn is_buy(direction: &Direction, opening: bool) -> bool {
+    (*direction == Direction::Long) == opening
+}
+
+/// Fill price after crossing the synthetic spread: buys fill above the oracle price, sells below.
+pub fn apply_half_spread(price: i64, is_buy: bool, half_spread_bps: u16) -> Result<i64> {
+    let half_spread = calculate_bps_share(price, half_spread_bps, Rounding::Up)?;
+    let fill_price = if is_buy {
+        price.checked_add(half_spread)
+    } else {
+        price.checked_sub(half_spread)
+    };
+
+    fill_price.ok_or(crate::errors::ErrorCode::MathOverflow.into())
+}
+
+pub fn calculate_taker_fee(notional: i64, taker_fee_bps: u16) -> Result<i64> {
+    calculate_bps_share(notional, taker_fee_bps, Rounding::Up)
+}
+
+/// Deducts a trading fee from the participant's paper balance.
+pub fn charge_fee(participant: &mut Participant, fee: i64) -> Result<()> {
+    participant.virtual_balance = participant
+        .virtual_balance
+        .checked_sub(fee)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+    participant.total_fees = participant
+        .total_fees
+        .checked_add(fee)
+        .ok_or(crate::errors::ErrorCode::MathOverflow)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorCode;
+    use crate::state::PayoutSchedule;
+    use crate::test_fixtures::{self, TestAccount, NOW, SOL_DECIMALS, USD};
+
+    const FEED_ID: [u8; 32] = [7; 32];
+
+    fn read_price(update: &PriceUpdateV2, max_price_age_secs: u64, max_conf_bps: u16) -> Result<(i64, i32)> {
+        read_price_from(PYTH_RECEIVER_PROGRAM_ID, update, max_price_age_secs, max_conf_bps)
+    }
+
+    fn read_price_from(
+        owner: Pubkey,
+        update: &PriceUpdateV2,
+        max_price_age_secs: u64,
+        max_conf_bps: u16,
+    ) -> Result<(i64, i32)> {
+        test_fixtures::use_clock();
+        let mut feed = TestAccount::new(Pubkey::new_unique(), owner, update);
+        get_price_and_exponent_from_pyth(&feed.info(), &FEED_ID, max_price_age_secs, max_conf_bps)
+    }
+
+    #[test]
+    fn pyth_price_within_age_and_confidence_is_read() {
+        let update = test_fixtures::price_update(FEED_ID, 15_000_000_000);
+        assert_eq!(read_price(&update, 60, 100).unwrap(), (15_000_000_000, -8));
+    }
+
+    #[test]
+    fn pyth_price_older_than_max_age_is_stale() {
+        let mut update = test_fixtures::price_update(FEED_ID, 15_000_000_000);
+        update.price_message.publish_time = NOW - 60;
+        read_price(&update, 60, 100).unwrap();
+
+        update.price_message.publish_time = NOW - 61;
+        assert_eq!(read_price(&update, 60, 100).unwrap_err(), ErrorCode::StaleOraclePrice.into());
+    }
+
+    #[test]
+    fn pyth_price_with_wide_confidence_is_rejected() {
+        // 1% of $150
+        let mut update = test_fixtures::price_update(FEED_ID, 15_000_000_000);
+        update.price_message.conf = 150_000_000;
+        read_price(&update, 60, 100).unwrap();
+
+        update.price_message.conf = 150_000_001;
+        assert_eq!(read_price(&update, 60, 100).unwrap_err(), ErrorCode::OracleConfidenceTooWide.into());
+    }
+
+    #[test]
+    fn price_feed_must_be_owned_by_a_known_oracle_program() {
+        let update = test_fixtures::price_update(FEED_ID, 15_000_000_000);
+        read_price_from(ER_PRICE_PROGRAM_ID, &update, 60, 100).unwrap();
+        assert_eq!(
+            read_price_from(Pubkey::new_unique(), &update, 60, 100).unwrap_err(),
+            ErrorCode::InvalidOraclePriceFeed.into()
+        );
+    }
+
+    #[test]
+    fn price_feed_must_carry_the_market_feed_id() {
+        let update = test_fixtures::price_update([8; 32], 15_000_000_000);
+        assert_eq!(read_price(&update, 60, 100).unwrap_err(), ErrorCode::OracleMismatch.into());
+    }
+
+    #[test]
+    fn partially_verified_receiver_updates_are_rejected() {
+        let mut update = test_fixtures::price_update(FEED_ID, 15_000_000_000);
+        update.verification_level = VerificationLevel::Partial { num_signatures: 5 };
+        assert_eq!(
+            read_price(&update, 60, 100).unwrap_err(),
+            ErrorCode::InsufficientOracleVerification.into()
+        );
+        // Only the Pyth receiver stores partially verified updates
+        read_price_from(ER_PRICE_PROGRAM_ID, &update, 60, 100).unwrap();
+    }
+
+    #[test]
+    fn div_with_rounding_floors_and_ceils() {
+        assert_eq!(div_with_rounding(7, 2, Rounding::Down).unwrap(), 3);
+        assert_eq!(div_with_rounding(7, 2, Rounding::Up).unwrap(), 4);
+        assert_eq!(div_with_rounding(6, 3, Rounding::Up).unwrap(), 2);
+        // Negative numerators round towards -inf / +inf, not towards zero
+        assert_eq!(div_with_rounding(-7, 2, Rounding::Down).unwrap(), -4);
+        assert_eq!(div_with_rounding(-7, 2, Rounding::Up).unwrap(), -3);
+    }
+
+    #[test]
+    fn div_with_rounding_rejects_non_positive_denominator() {
+        assert_eq!(div_with_rounding(1, 0, Rounding::Down).unwrap_err(), ErrorCode::DivisionByZero.into());
+        assert_eq!(div_with_rounding(1, -1, Rounding::Down).unwrap_err(), ErrorCode::MathOverflow.into());
+    }
+
+    #[test]
+    fn price_rounding_is_against_the_trader() {
+        assert!(price_rounding(true) == Rounding::Up);
+        assert!(price_rounding(false) == Rounding::Down);
+    }
+
+    #[test]
+    fn scale_price_to_quote_decimals_shifts_exponent() {
+        // $150.12345678 at exponent -8
+        assert_eq!(scale_price_to_quote_decimals(15_012_345_678, -8, Rounding::Down).unwrap(), 150_123_456);
+        assert_eq!(scale_price_to_quote_decimals(15_012_345_678, -8, Rounding::Up).unwrap(), 150_123_457);
+        assert_eq!(scale_price_to_quote_decimals(15_000_000_000, -8, Rounding::Up).unwrap(), 150 * USD);
+        assert_eq!(scale_price_to_quote_decimals(150_123_456, -6, Rounding::Up).unwrap(), 150_123_456);
+        assert_eq!(scale_price_to_quote_decimals(12_345, -4, Rounding::Down).unwrap(), 1_234_500);
+        assert_eq!(
+            scale_price_to_quote_decimals(i64::MAX, 0, Rounding::Down).unwrap_err(),
+            ErrorCode::MathOverflow.into()
+        );
+    }
+
+    #[test]
+    fn required_margin_rounds_up() {
+        assert_eq!(calculate_required_margin(1_000_000, 10).unwrap(), 100_000);
+        assert_eq!(calculate_required_margin(1_000_001, 10).unwrap(), 100_001);
+    }
+
+    #[test]
+    fn notional_and_price_round_trip() {
+        // 2 SOL at $150
+        let notional = calculate_notional(150 * USD, 2_000_000, SOL_DECIMALS).unwrap();
+        assert_eq!(notional, 300 * USD);
+        assert_eq!(calculate_price_from_notional_and_size(notional, 2_000_000, SOL_DECIMALS).unwrap(), 150 * USD);
+        assert_eq!(
+            calculate_price_from_notional_and_size(notional, 0, SOL_DECIMALS).unwrap_err(),
+            ErrorCode::DivisionByZero.into()
+        );
+        assert_eq!(
+            calculate_notional(i64::MAX, 2_000_000, 0).unwrap_err(),
+            ErrorCode::MathOverflow.into()
+        );
+    }
+
+    #[test]
+    fn unrealized_pnl_follows_direction() {
+        let notional = 300 * USD;
+        assert_eq!(
+            calculate_unrealized_pnl(notional, 160 * USD, 2_000_000, SOL_DECIMALS, Direction::Long).unwrap(),
+            20 * USD
+        );
+        assert_eq!(
+            calculate_unrealized_pnl(notional, 160 * USD, 2_000_000, SOL_DECIMALS, Direction::Short).unwrap(),
+            -20 * USD
+        );
+        assert_eq!(
+            calculate_unrealized_pnl(notional, i64::MAX, i64::MAX, SOL_DECIMALS, Direction::Long).unwrap_err(),
+            ErrorCode::MathOverflow.into()
+        );
+    }
+
+    #[test]
+    fn bps_share_rounds_as_requested() {
+        assert_eq!(calculate_bps_share(1_000_000, 250, Rounding::Down).unwrap(), 25_000);
+        assert_eq!(calculate_bps_share(999, 5, Rounding::Down).unwrap(), 0);
+        assert_eq!(calculate_bps_share(999, 5, Rounding::Up).unwrap(), 1);
+    }
+
+    #[test]
+    fn liquidation_price_for_isolated_positions() {
+        // $300 notional at 10x: $30 margin, 2.5% maintenance margin
+        let long = test_fixtures::position(Direction::Long, MarginMode::Isolated, 150 * USD, 2_000_000);
+        // (300 - 30) / 0.975 / 2, rounded up towards the entry price
+        assert_eq!(calculate_liquidation_price(&long, 250).unwrap(), 138_461_539);
+
+        let short = test_fixtures::position(Direction::Short, MarginMode::Isolated, 150 * USD, 2_000_000);
+        // (300 + 30) / 1.025 / 2, rounded down towards the entry price
+        assert_eq!(calculate_liquidation_price(&short, 250).unwrap(), 160_975_609);
+
+        // A higher maintenance margin moves the liquidation price towards the entry price
+        assert!(calculate_liquidation_price(&long, 500).unwrap() > 138_461_539);
+    }
+
+    #[test]
+    fn liquidation_price_is_zero_for_cross_or_empty_positions() {
+        let cross = test_fixtures::position(Direction::Long, MarginMode::Cross, 150 * USD, 2_000_000);
+        assert_eq!(calculate_liquidation_price(&cross, 250).unwrap(), 0);
+
+        let empty = test_fixtures::position(Direction::Long, MarginMode::Isolated, 150 * USD, 0);
+        assert_eq!(calculate_liquidation_price(&empty, 250).unwrap(), 0);
+    }
+
+    #[test]
+    fn half_spread_fills_buys_above_and_sells_below() {
+        // 5 bps of $150
+        assert_eq!(apply_half_spread(150 * USD, true, 5).unwrap(), 150_075_000);
+        assert_eq!(apply_half_spread(150 * USD, false, 5).unwrap(), 149_925_000);
+        assert_eq!(apply_half_spread(150 * USD, true, 0).unwrap(), 150 * USD);
+        // The spread rounds up, against the trader
+        assert_eq!(apply_half_spread(100_001, true, 1).unwrap(), 100_012);
+    }
+
+    #[test]
+    fn league_fees_override_the_market_unless_unset() {
+        let market = test_fixtures::market();
+        let mut league = test_fixtures::league(PayoutSchedule::Tiered { bps: vec![10_000] }, 0);
+        let fees = resolve_trading_fees(&league, &market);
+        assert_eq!((fees.taker_fee_bps, fees.half_spread_bps), (5, 5));
+
+        // A zero override makes trading free, not the market default
+        league.taker_fee_bps = 0;
+        league.half_spread_bps = 20;
+        let fees = resolve_trading_fees(&league, &market);
+        assert_eq!((fees.taker_fee_bps, fees.half_spread_bps), (0, 20));
+    }
+
+    #[test]
+    fn opening_longs_and_closing_shorts_buy() {
+        assert!(is_buy(&Direction::Long, true));
+        assert!(!is_buy(&Direction::Long, false));
+        assert!(!is_buy(&Direction::Short, true));
+        assert!(is_buy(&Direction::Short, false));
+    }
+
+    #[test]
+    fn taker_fee_is_charged_to_the_balance() {
+        // 5 bps of $300, rounded up
+        assert_eq!(calculate_taker_fee(300 * USD, 5).unwrap(), 150_000);
+        assert_eq!(calculate_taker_fee(100_001, 1).unwrap(), 11);
+
+        let mut participant = test_fixtures::participant(1_000 * USD);
+        charge_fee(&mut participant, 150_000).unwrap();
+        charge_fee(&mut participant, 11).unwrap();
+        assert_eq!(participant.virtual_balance, 1_000 * USD - 150_011);
+        assert_eq!(participant.total_fees, 150_011);
+    }
 }
@@ -13,6 +13,7 @@ import {
   ASSOCIATED_TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 import { getAuthToken } from "./tee-getAuthToken";
+import { expect } from "chai";
 
 const RPC_URL = "https://api.devnet.solana.com";
 const WS_URL = "wss://api.devnet.solana.com";
@@ -36,6 +37,19 @@ const [price_feed_pda] = anchor.web3.PublicKey.findProgramAddressSync(
   new anchor.web3.PublicKey(PYTH_PROGRAM_ID)
 );
 
+// PriceUpdateV2 layout: discriminator (8), write authority (32), verification level,
+// then the price message starting with feed id (32) and price (i64).
+// VerificationLevel is Partial { num_signatures: u8 } (tag 0, 2 bytes) or Full (tag 1, 1 byte).
+const decodePriceUpdate = (data: Buffer) => {
+  const VERIFICATION_LEVEL_OFFSET = 40;
+  const verificationLevelSize = data[VERIFICATION_LEVEL_OFFSET] === 0 ? 2 : 1;
+  const feedIdOffset = VERIFICATION_LEVEL_OFFSET + verificationLevelSize;
+  return {
+    feedId: Array.from(data.subarray(feedIdOffset, feedIdOffset + 32)),
+    price: data.readBigInt64LE(feedIdOffset + 32),
+  };
+};
+
 const ENTRY_TOKEN_MINT = "6kyfSqqp9xE4etzxeKibDfSW1rmAtCdZDTndEKoCxTfw";
 const ENTRY_TOKEN_DECIMALS = 6;
 const ENTRY_AMOUNT = 1000000000; // 1,000 entry token
@@ -184,16 +198,8 @@ describe("tdf-program", async () => {
 
   it("Check Price Feed", async () => {
     const pythAccountInfo = await mbConnection.getAccountInfo(price_feed_pda);
-    const PRICE_OFFSET = 73;
-
-    const dataView = new DataView(
-      pythAccountInfo.data.buffer,
-      pythAccountInfo.data.byteOffset,
-      pythAccountInfo.data.length
-    );
-    const priceInt =
-      Number(dataView.getBigUint64(PRICE_OFFSET, true)) *
-      Math.pow(10, PYTH_EXPONENT);
+    const { price } = decodePriceUpdate(pythAccountInfo.data);
+    const priceInt = Number(price) * Math.pow(10, PYTH_EXPONENT);
     console.log("SOL price:", priceInt);
   });
 
@@ -205,12 +211,22 @@ describe("tdf-program", async () => {
 
     if (!existingMarket) {
       console.log("Creating Market...");
+      // Feed id stored in the price feed account
+      const pythAccountInfo = await mbConnection.getAccountInfo(price_feed_pda);
+      const { feedId } = decodePriceUpdate(pythAccountInfo.data);
+
       const tx = await program.methods
         .createMarket(
           // @ts-ignore
           Buffer.from("SOLUSD"),
+          feedId,
           SOL_DECIMALS,
-          20
+          20,
+          new anchor.BN(60), // max price age in seconds
+          100, // max confidence 1%
+          250, // maintenance margin 2.5%
+          5, // taker fee 0.05%
+          5 // half spread 0.05%
         )
         .accounts({
           // @ts-ignore
@@ -240,7 +256,12 @@ describe("tdf-program", async () => {
         Buffer.from("SOLUSD"),
         SOL_DECIMALS,
         true,
-        20
+        20,
+        new anchor.BN(60), // max price age in seconds
+        100, // max confidence 1%
+        250, // maintenance margin 2.5%
+        5, // taker fee 0.05%
+        5 // half spread 0.05%
       )
       .accounts({
         // @ts-ignore
@@ -336,10 +357,16 @@ describe("tdf-program", async () => {
           new anchor.BN(VIRTUAL_ON_DEPOSIT),
           new anchor.BN(startTime),
           new anchor.BN(endTime),
+          new anchor.BN(0), // no late joins
           "https://example.com",
           100,
+          2,
           20,
-          5
+          5,
+          { tiered: { bps: [5000, 3000, 2000] } },
+          { hedge: {} },
+          0xffff, // market taker fee
+          0xffff // market half spread
         )
         .accounts({
           // @ts-ignore
@@ -355,6 +382,9 @@ describe("tdf-program", async () => {
             ASSOCIATED_TOKEN_PROGRAM_ID
           ),
         })
+        .remainingAccounts([
+          { pubkey: marketPda, isWritable: false, isSigner: false },
+        ])
         .transaction();
 
       const signature = await sendMagicTransaction(routerConnection, tx, [
@@ -367,27 +397,6 @@ describe("tdf-program", async () => {
     }
   });
 
-  it("Start League", async () => {
-    // Check league status
-    const leagueAccount = await program.account.league.fetch(leaguePda);
-    if (leagueAccount.status.toString() === "pending") {
-      const tx = await program.methods
-        .startLeague()
-        .accounts({
-          league: leaguePda,
-          user: anchor.Wallet.local().publicKey,
-        })
-        .transaction();
-
-      const signature = await sendMagicTransaction(routerConnection, tx, [
-        anchor.Wallet.local().payer,
-      ]);
-      console.log("✅ Started League! Signature:", signature);
-    } else {
-      console.log("ℹ️ League is not pending, skipping start league");
-    }
-  });
-
   it("Join League", async () => {
     // Check if participant exists
     const participantAccount = await program.account.participant.fetchNullable(
@@ -426,6 +435,27 @@ describe("tdf-program", async () => {
     }
   });
 
+  it("Start League", async () => {
+    // Check league status
+    const leagueAccount = await program.account.league.fetch(leaguePda);
+    if (leagueAccount.status.toString() === "pending") {
+      const tx = await program.methods
+        .startLeague()
+        .accounts({
+          league: leaguePda,
+          user: anchor.Wallet.local().publicKey,
+        })
+        .transaction();
+
+      const signature = await sendMagicTransaction(routerConnection, tx, [
+        anchor.Wallet.local().payer,
+      ]);
+      console.log("✅ Started League! Signature:", signature);
+    } else {
+      console.log("ℹ️ League is not pending, skipping start league");
+    }
+  });
+
   it("Delegate Participant", async () => {
     const delegated = await getDelegationStatus(
       routerConnection,
@@ -452,6 +482,103 @@ describe("tdf-program", async () => {
     }
   });
 
+  describe("Open Isolated Position Scenario", () => {
+    let isolatedPositionSeq: anchor.BN;
+    let isolatedPositionPda: anchor.web3.PublicKey;
+
+    it("Init Unopened Position and Delegate", async () => {
+      // The delegated participant holds the next position sequence
+      const participantInfo = await routerConnection.getAccountInfo(
+        participantPda
+      );
+      const participant = program.coder.accounts.decode(
+        "participant",
+        participantInfo.data
+      );
+      isolatedPositionSeq = participant.currentPositionSeq;
+      [isolatedPositionPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("position"),
+          leaguePda.toBuffer(),
+          anchor.Wallet.local().publicKey.toBuffer(),
+          isolatedPositionSeq.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+
+      const initIx = await program.methods
+        .initUnopenedPosition(leaguePda, isolatedPositionSeq)
+        .accounts({
+          // @ts-ignore
+          position: isolatedPositionPda,
+          participant: participantPda,
+          leagueAccount: leaguePda,
+          market: marketPda,
+          user: anchor.Wallet.local().publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .instruction();
+      const delegateIx = await program.methods
+        .delegateUnopenedPosition(participantPda, isolatedPositionSeq)
+        .accounts({
+          user: anchor.Wallet.local().publicKey,
+          league: leaguePda,
+          participant: participantPda,
+          position: isolatedPositionPda,
+        })
+        .instruction();
+
+      const signature = await sendAndConfirmTransaction(
+        connection,
+        new anchor.web3.Transaction().add(initIx, delegateIx),
+        [anchor.Wallet.local().payer]
+      );
+      console.log("✅ Init and Delegated Isolated Position! Signature:", signature);
+
+      await sleepWithAnimation(5);
+    });
+
+    it("Open Isolated Position", async () => {
+      const tx = await program.methods
+        .openPosition(
+          isolatedPositionSeq,
+          { long: {} },
+          new anchor.BN(1000000), // 1 SOL
+          10,
+          { isolated: {} },
+          new anchor.BN(0), // no stop loss
+          new anchor.BN(0) // no take profit
+        )
+        .accounts({
+          user: anchor.Wallet.local().publicKey,
+          // @ts-ignore
+          position: isolatedPositionPda,
+          priceFeed: price_feed_pda,
+          league: leaguePda,
+          market: marketPda,
+          participant: participantPda,
+        })
+        .transaction();
+
+      const signature = await sendMagicTransaction(routerConnection, tx, [
+        anchor.Wallet.local().payer,
+      ]);
+      console.log("✅ Opened Isolated Position! Signature:", signature);
+
+      // A 10x long is liquidated below its entry price
+      const positionInfo = await routerConnection.getAccountInfo(
+        isolatedPositionPda
+      );
+      const position = program.coder.accounts.decode(
+        "position",
+        positionInfo.data
+      );
+      logPosition(position);
+      expect(position.liquidationPrice.gtn(0)).to.equal(true);
+      expect(position.liquidationPrice.lt(position.entryPrice)).to.equal(true);
+    });
+  });
+
   // describe("Open Public Position Scenario", () => {
   //   it("Init Unopened Position and Delegate", async () => {
   //     // Check if position exists
@@ -467,14 +594,14 @@ describe("tdf-program", async () => {
   //       const tx = await program.methods
   //         .initUnopenedPosition(
   //           leaguePda,
-  //           new anchor.BN(currentPositionSeq),
-  //           marketPda,
-  //           SOL_DECIMALS
+  //           new anchor.BN(currentPositionSeq)
   //         )
   //         .accounts({
   //           // @ts-ignore
   //           position: positionPda,
-  //           priceFeed: price_feed_pda,
+  //           participant: participantPda,
+  //           leagueAccount: leaguePda,
+  //           market: marketPda,
   //           user: anchor.Wallet.local().publicKey,
   //           systemProgram: anchor.web3.SystemProgram.programId,
   //         })
@@ -511,6 +638,7 @@ describe("tdf-program", async () => {
   //         .accounts({
   //           user: anchor.Wallet.local().publicKey,
   //           league: leaguePda,
+  //           participant: participantPda,
   //           position: positionPda,
   //         })
   //         .transaction();
@@ -552,7 +680,10 @@ describe("tdf-program", async () => {
   //           new anchor.BN(currentPositionSeq),
   //           { long: {} },
   //           new anchor.BN(1000000), // 1 SOL
-  //           1
+  //           1,
+  //           { cross: {} },
+  //           new anchor.BN(0), // no stop loss
+  //           new anchor.BN(0) // no take profit
   //         )
   //         .accounts({
   //           user: anchor.Wallet.local().publicKey,
@@ -590,6 +721,11 @@ describe("tdf-program", async () => {
   //           isSigner: false,
   //         },
   //         {
+  //           pubkey: marketPda,
+  //           isWritable: false,
+  //           isSigner: false,
+  //         },
+  //         {
   //           pubkey: price_feed_pda,
   //           isWritable: false,
   //           isSigner: false,
@@ -621,6 +757,11 @@ describe("tdf-program", async () => {
   //           isSigner: false,
   //         },
   //         {
+  //           pubkey: marketPda,
+  //           isWritable: false,
+  //           isSigner: false,
+  //         },
+  //         {
   //           pubkey: price_feed_pda,
   //           isWritable: false,
   //           isSigner: false,
@@ -656,7 +797,10 @@ describe("tdf-program", async () => {
 
   //   it("Close Position = Commit and Undelegate Position, Commit Participant", async () => {
   //     const tx = await program.methods
-  //       .closePosition(new anchor.BN(currentPositionSeq))
+  //       .closePosition(
+  //         new anchor.BN(currentPositionSeq),
+  //         new anchor.BN(1000000) // close the full 1 SOL
+  //       )
   //       .accounts({
   //         user: anchor.Wallet.local().publicKey,
   //         // @ts-ignore
@@ -921,15 +1065,15 @@ describe("tdf-program", async () => {
     //   const initIx = await program.methods
     //     .initUnopenedPosition(
     //       leaguePda,
-    //       new anchor.BN(privatePositionSeq),
-    //       marketPda,
-    //       SOL_DECIMALS
+    //       new anchor.BN(privatePositionSeq)
     //     )
     //     .accounts({
     //       user: anchor.Wallet.local().publicKey,
     //       // @ts-ignore
     //       position: privatePositionPda,
-    //       priceFeed: price_feed_pda,
+    //       participant: participantPda,
+    //       leagueAccount: leaguePda,
+    //       market: marketPda,
     //       systemProgram: anchor.web3.SystemProgram.programId,
     //     })
     //     .instruction();
@@ -963,6 +1107,7 @@ describe("tdf-program", async () => {
     //     .accounts({
     //       user: anchor.Wallet.local().publicKey,
     //       league: leaguePda,
+    //       participant: participantPda,
     //       position: privatePositionPda,
     //     })
     //     .instruction();
@@ -988,7 +1133,10 @@ describe("tdf-program", async () => {
           new anchor.BN(privatePositionSeq),
           { long: {} },
           new anchor.BN(1000000),
-          10
+          10,
+          { cross: {} },
+          new anchor.BN(0), // no stop loss
+          new anchor.BN(0) // no take profit
         )
         .accounts({
           user: anchor.Wallet.local().publicKey,
@@ -1052,6 +1200,246 @@ describe("tdf-program", async () => {
     });
   });
 
+  describe("League Settlement Scenario", () => {
+    const user = anchor.Wallet.local().publicKey;
+    const userEntryTokenAccountPda = getAssociatedTokenAddressSync(
+      entryTokenMintPda,
+      user,
+      true
+    );
+
+    const leaguePdas = (leagueId: string) => {
+      const [league] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("league"), user.toBuffer(), Buffer.from(leagueId)],
+        program.programId
+      );
+      const [leaderboard] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("leaderboard"), league.toBuffer()],
+        program.programId
+      );
+      const [participant] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("participant"), league.toBuffer(), user.toBuffer()],
+        program.programId
+      );
+      const rewardVault = getAssociatedTokenAddressSync(
+        entryTokenMintPda,
+        league,
+        true
+      );
+      return { league, leaderboard, participant, rewardVault };
+    };
+
+    const send = (tx: Transaction) =>
+      sendMagicTransaction(routerConnection, tx, [anchor.Wallet.local().payer]);
+
+    const tokenBalance = async () =>
+      new anchor.BN(
+        (await connection.getTokenAccountBalance(userEntryTokenAccountPda))
+          .value.amount
+      );
+
+    const expectProgramError = async (promise: Promise<any>, code: string) => {
+      try {
+        await promise;
+      } catch (error) {
+        const logs: string[] = error.logs || [];
+        expect(logs.join("\n") + error.toString()).to.contain(code);
+        return;
+      }
+      expect.fail(`expected ${code}`);
+    };
+
+    const createAndJoinLeague = async (
+      leagueId: string,
+      minParticipants: number
+    ) => {
+      const pdas = leaguePdas(leagueId);
+      const startTime = Math.floor(new Date().getTime() / 1000);
+
+      await send(
+        await program.methods
+          .createLeague(
+            // @ts-ignore
+            leagueId,
+            [marketPda],
+            new anchor.BN(ENTRY_AMOUNT),
+            new anchor.BN(VIRTUAL_ON_DEPOSIT),
+            new anchor.BN(startTime),
+            new anchor.BN(startTime + 86400),
+            new anchor.BN(0), // no late joins
+            "https://example.com",
+            100,
+            minParticipants,
+            20,
+            1,
+            { tiered: { bps: [10000] } },
+            { hedge: {} },
+            0xffff, // market taker fee
+            0xffff // market half spread
+          )
+          .accounts({
+            // @ts-ignore
+            creator: user,
+            league: pdas.league,
+            leaderboard: pdas.leaderboard,
+            entryTokenMint: entryTokenMintPda,
+            rewardVault: pdas.rewardVault,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: new anchor.web3.PublicKey(TOKEN_PROGRAM_ID),
+            associatedTokenProgram: new anchor.web3.PublicKey(
+              ASSOCIATED_TOKEN_PROGRAM_ID
+            ),
+          })
+          .remainingAccounts([
+            { pubkey: marketPda, isWritable: false, isSigner: false },
+          ])
+          .transaction()
+      );
+
+      await send(
+        await program.methods
+          .joinLeague()
+          .accounts({
+            league: pdas.league,
+            // @ts-ignore
+            participant: pdas.participant,
+            rewardVault: pdas.rewardVault,
+            userEntryTokenAccount: userEntryTokenAccountPda,
+            user,
+            tokenProgram: new anchor.web3.PublicKey(TOKEN_PROGRAM_ID),
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .transaction()
+      );
+
+      return pdas;
+    };
+
+    before(async () => {
+      // Entry fees for the two leagues joined below
+      const tokenAccount = await getOrCreateAssociatedTokenAccount(
+        connection,
+        anchor.Wallet.local().payer,
+        entryTokenMintPda,
+        user
+      );
+      if (tokenAccount.amount < 2 * ENTRY_AMOUNT) {
+        await mintTo(
+          connection,
+          anchor.Wallet.local().payer,
+          entryTokenMintPda,
+          tokenAccount.address,
+          anchor.Wallet.local().payer,
+          2 * ENTRY_AMOUNT
+        );
+      }
+    });
+
+    it("Claim Reward once the league is closed, and only once", async () => {
+      const pdas = await createAndJoinLeague("claim-" + Date.now(), 1);
+
+      await send(
+        await program.methods
+          .startLeague()
+          .accounts({ league: pdas.league, user })
+          .transaction()
+      );
+      await send(
+        await program.methods
+          .updateLeaderboardWithParticipant()
+          .accounts({
+            // @ts-ignore
+            leaderboard: pdas.leaderboard,
+            league: pdas.league,
+            participant: pdas.participant,
+          })
+          .transaction()
+      );
+      // The treasury is the local wallet, see GlobalConfig initialization
+      await send(
+        await program.methods
+          .closeLeague()
+          .accounts({
+            league: pdas.league,
+            rewardVault: pdas.rewardVault,
+            // @ts-ignore
+            globalConfig: global_config_pda,
+            treasuryTokenAccount: userEntryTokenAccountPda,
+            user,
+            tokenProgram: new anchor.web3.PublicKey(TOKEN_PROGRAM_ID),
+          })
+          .transaction()
+      );
+
+      const leagueAccount = await program.account.league.fetch(pdas.league);
+      const claimTx = async () =>
+        await program.methods
+          .claimReward()
+          .accounts({
+            league: pdas.league,
+            // @ts-ignore
+            leaderboard: pdas.leaderboard,
+            participant: pdas.participant,
+            rewardVault: pdas.rewardVault,
+            userEntryTokenAccount: userEntryTokenAccountPda,
+            user,
+            tokenProgram: new anchor.web3.PublicKey(TOKEN_PROGRAM_ID),
+          })
+          .transaction();
+
+      // Sole participant takes the whole pot left after the protocol fee
+      const before = await tokenBalance();
+      await send(await claimTx());
+      const after = await tokenBalance();
+      expect(after.sub(before).toString()).to.equal(
+        leagueAccount.totalRewardAmount.toString()
+      );
+      expect(
+        (await program.account.participant.fetch(pdas.participant)).claimed
+      ).to.equal(true);
+
+      await expectProgramError(send(await claimTx()), "RewardAlreadyClaimed");
+    });
+
+    it("Refund Entry once the league is cancelled, and only once", async () => {
+      // Minimum of 2 participants is never met
+      const pdas = await createAndJoinLeague("refund-" + Date.now(), 2);
+
+      await send(
+        await program.methods
+          .cancelLeague()
+          .accounts({
+            league: pdas.league,
+            // @ts-ignore
+            globalConfig: global_config_pda,
+            user,
+          })
+          .transaction()
+      );
+
+      const refundTx = async () =>
+        await program.methods
+          .refundEntry()
+          .accounts({
+            league: pdas.league,
+            // @ts-ignore
+            participant: pdas.participant,
+            rewardVault: pdas.rewardVault,
+            userEntryTokenAccount: userEntryTokenAccountPda,
+            user,
+            tokenProgram: new anchor.web3.PublicKey(TOKEN_PROGRAM_ID),
+          })
+          .transaction();
+
+      const before = await tokenBalance();
+      await send(await refundTx());
+      const after = await tokenBalance();
+      expect(after.sub(before).toString()).to.equal(ENTRY_AMOUNT.toString());
+
+      await expectProgramError(send(await refundTx()), "EntryAlreadyRefunded");
+    });
+  });
+
   describe("Private Resource Example", () => {
     
 